@@ -0,0 +1,73 @@
+use crate::piece::Piece;
+use std::time::Duration;
+
+pub const DEFAULT_DURATION: Duration = Duration::from_millis(200);
+
+// Progress of an animation, which is advanced by the frame time
+#[derive(Copy, Clone, Debug)]
+pub struct Animation {
+    elapsed: Duration,
+    duration: Duration,
+}
+
+impl Animation {
+    pub fn new(duration: Duration) -> Animation {
+        Animation {
+            elapsed: Duration::ZERO,
+            duration,
+        }
+    }
+
+    pub fn advance(&mut self, delta_time: Duration) {
+        self.elapsed = (self.elapsed + delta_time).min(self.duration);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    // Eased progress from 0.0 to 1.0
+    pub fn progress(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+
+        ease_in_out(self.elapsed.as_secs_f32() / self.duration.as_secs_f32())
+    }
+}
+
+// A piece which slides from one square to another
+pub struct MoveAnimation {
+    pub from: (u8, u8),
+    pub to: (u8, u8),
+    pub animation: Animation,
+}
+
+// A captured piece which fades out
+pub struct CaptureAnimation {
+    pub piece: Piece,
+    pub animation: Animation,
+}
+
+// The board which rotates from one angle to another (in degrees, clock-wise)
+pub struct RotationAnimation {
+    pub from: f32,
+    pub to: f32,
+    pub animation: Animation,
+}
+
+// Cubic easing: slow at the start and at the end
+// Reference: https://easings.net/#easeInOutCubic
+pub fn ease_in_out(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+
+    if t < 0.5 {
+        4.0 * t * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+    }
+}
+
+pub fn lerp(from: f32, to: f32, t: f32) -> f32 {
+    from + (to - from) * t
+}
@@ -0,0 +1,364 @@
+use crate::piece::PieceColor;
+use std::sync::LazyLock;
+
+// Set of squares (bit board_y * 8 + board_x)
+pub type Bitboard = u64;
+
+const KNIGHT_OFFSETS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+const KING_OFFSETS: [(i8, i8); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (-1, 1), (-1, -1), (1, -1)];
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+// Magic numbers which map every relevant occupancy of a square to a distinct index of its attack
+// table. They have been found by trial and error with random numbers
+const ROOK_MAGICS: [u64; 64] = [
+    0x7080_0080_2010_4008,
+    0x2040_0040_2000_1009,
+    0x0a00_1042_0020_0880,
+    0x0280_1000_8108_0184,
+    0x0200_1008_0502_0020,
+    0xc600_0824_0200_1001,
+    0x0880_0200_0080_0100,
+    0xa080_0080_0448_2500,
+    0x0088_8010_2080_4000,
+    0x0001_4010_0020_0240,
+    0x0001_8020_0010_0180,
+    0x6410_8010_0008_0080,
+    0x0002_8080_0800_0400,
+    0x2103_0012_0804_0100,
+    0x0004_0010_0104_b208,
+    0x4482_0001_08a0_4402,
+    0x0a00_2880_0c40_0080,
+    0x0050_0140_0040_2000,
+    0x0900_1100_2001_0840,
+    0x0000_2200_4200_1008,
+    0x0004_0080_8008_0004,
+    0x0c04_0080_0480_0200,
+    0x0010_0400_01d0_0802,
+    0x0028_0a00_0050_810c,
+    0x7100_4000_8020_8000,
+    0x4001_0081_0020_4000,
+    0x0000_4011_0020_0500,
+    0x0000_1001_0020_0901,
+    0x0900_0801_0004_1100,
+    0x0432_0400_8002_0080,
+    0x1001_1044_0002_0188,
+    0x000c_0046_0009_2184,
+    0x0200_8040_0080_0024,
+    0xe010_0020_0240_0040,
+    0xa010_2000_8080_1000,
+    0x00d0_0011_0300_2088,
+    0x0914_0004_8080_0800,
+    0x0080_0200_8080_0400,
+    0x4840_0110_4400_0802,
+    0xc800_2084_0200_0041,
+    0x0800_2080_4000_8000,
+    0x0060_0020_5000_4000,
+    0x2400_2000_4101_0011,
+    0x1010_0008_0010_8080,
+    0x0908_0400_0800_8080,
+    0x3802_0004_0080_8002,
+    0x0001_000a_0009_0004,
+    0x0848_0089_0842_0014,
+    0x00c0_0020_8000_5480,
+    0x0003_0438_8140_0100,
+    0x0890_0020_0010_8080,
+    0x0802_8010_0208_0480,
+    0x0002_0021_0410_0a00,
+    0x0063_0024_00a8_2300,
+    0x0088_1110_0258_0400,
+    0x8020_0400_4100_8200,
+    0x0001_2180_00c3_1101,
+    0x2001_0012_0020_408a,
+    0x9020_0810_4020_0301,
+    0x0022_1500_28a0_1001,
+    0x4102_0020_1008_0402,
+    0x0001_0004_0002_0801,
+    0x8480_0201_1098_1004,
+    0x0428_0104_0820_4082,
+];
+const BISHOP_MAGICS: [u64; 64] = [
+    0xc084_2024_0050_8104,
+    0x4010_0200_810a_1080,
+    0x0008_00cc_0080_2000,
+    0x0224_40c1_8200_0220,
+    0x0b01_1041_0000_0108,
+    0x4300_9004_2040_0060,
+    0xc100_8088_a840_0a00,
+    0x0002_0021_1802_3000,
+    0x0001_1002_2808_408c,
+    0x8000_2082_0082_0080,
+    0x0401_5081_0200_2040,
+    0x0802_0240_8104_0002,
+    0x2200_0404_2000_4011,
+    0x0288_0101_6010_00c0,
+    0x0400_0484_0421_0484,
+    0x6020_0054_0401_3800,
+    0x0405_0040_8802_0400,
+    0x0820_4091_0441_0040,
+    0x2004_0082_0400_1200,
+    0x0008_0004_2040_1001,
+    0x1020_8204_06a0_0400,
+    0x080a_0101_0046_2a08,
+    0x2001_0400_a808_0221,
+    0x0206_0000_4044_1480,
+    0x0022_1040_40c4_0824,
+    0x1221_1000_041c_0860,
+    0x4408_0800_0400_4010,
+    0x4520_0800_2100_4088,
+    0x0080_4040_0401_0040,
+    0x1308_8202_4288_0400,
+    0x0004_0404_1842_0201,
+    0x0101_020c_0020_8440,
+    0x0250_5008_6070_0200,
+    0x0021_0808_4442_5082,
+    0x3201_0401_0008_8800,
+    0x0020_4008_0810_8200,
+    0x0000_5440_4014_0100,
+    0x0010_8200_8004_1001,
+    0x0028_00a1_0040_8800,
+    0x040c_0081_2688_8402,
+    0x0048_2410_2a14_0402,
+    0x0422_0812_0800_0311,
+    0x000a_2102_4048_8808,
+    0x4000_0022_1800_4c00,
+    0x0000_0819_0400_4040,
+    0x03a0_0600_4940_6200,
+    0x0202_0801_0c07_8104,
+    0x1002_060a_4200_9111,
+    0x8022_00a4_04c0_ac00,
+    0x0101_1101_2120_0800,
+    0x0000_4200_4208_0904,
+    0x1000_0150_8404_0401,
+    0x1000_0812_1202_1042,
+    0x0000_0aa0_0806_2000,
+    0x00a0_2102_0200_5900,
+    0x0108_1129_1a22_0050,
+    0x7200_c048_0821_1040,
+    0x4400_0866_0504_2100,
+    0xec10_0020_2084_1084,
+    0x1000_1802_0484_0404,
+    0x0004_4114_0405_0401,
+    0x0c01_8a08_1084_0820,
+    0x1808_0808_1000_8210,
+    0x4010_0138_0083_8208,
+];
+
+static ATTACK_TABLES: LazyLock<AttackTables> = LazyLock::new(AttackTables::new);
+
+// Reference: https://www.chessprogramming.org/Magic_Bitboards
+#[derive(Copy, Clone, Default)]
+struct Magic {
+    // Squares whose occupancy affects the attacks (without the edges of the board)
+    mask: Bitboard,
+    magic: u64,
+    shift: u32,
+    // Start of the attacks of this square in AttackTables::sliding_attacks
+    offset: usize,
+}
+
+impl Magic {
+    fn index(&self, occupancy: Bitboard) -> usize {
+        self.offset + ((occupancy & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+struct AttackTables {
+    knight_attacks: [Bitboard; 64],
+    king_attacks: [Bitboard; 64],
+    // pawn_attacks[color][square]
+    pawn_attacks: [[Bitboard; 64]; 2],
+    bishop_magics: [Magic; 64],
+    rook_magics: [Magic; 64],
+    sliding_attacks: Vec<Bitboard>,
+}
+
+impl AttackTables {
+    fn new() -> AttackTables {
+        let mut tables = AttackTables {
+            knight_attacks: [0; 64],
+            king_attacks: [0; 64],
+            pawn_attacks: [[0; 64]; 2],
+            bishop_magics: [Magic::default(); 64],
+            rook_magics: [Magic::default(); 64],
+            sliding_attacks: Vec::new(),
+        };
+
+        for square in 0..64 {
+            tables.knight_attacks[square] = step_attacks(square, &KNIGHT_OFFSETS);
+            tables.king_attacks[square] = step_attacks(square, &KING_OFFSETS);
+            tables.pawn_attacks[PieceColor::White.index()][square] =
+                step_attacks(square, &[(-1, 1), (1, 1)]);
+            tables.pawn_attacks[PieceColor::Black.index()][square] =
+                step_attacks(square, &[(-1, -1), (1, -1)]);
+
+            tables.bishop_magics[square] =
+                tables.add_sliding_attacks(square, BISHOP_MAGICS[square], &BISHOP_DIRECTIONS);
+            tables.rook_magics[square] =
+                tables.add_sliding_attacks(square, ROOK_MAGICS[square], &ROOK_DIRECTIONS);
+        }
+
+        tables
+    }
+
+    // Fills the attack table of a square for every relevant occupancy
+    fn add_sliding_attacks(&mut self, square: usize, magic: u64, directions: &[(i8, i8)]) -> Magic {
+        let mask = relevant_occupancy_mask(square, directions);
+        let magic = Magic {
+            mask,
+            magic,
+            shift: 64 - mask.count_ones(),
+            offset: self.sliding_attacks.len(),
+        };
+
+        self.sliding_attacks
+            .resize(magic.offset + (1 << mask.count_ones()), 0);
+
+        // Enumerate all subsets of the mask (Carry-Rippler)
+        let mut occupancy: Bitboard = 0;
+
+        loop {
+            self.sliding_attacks[magic.index(occupancy)] =
+                slide_attacks(square, occupancy, directions);
+
+            occupancy = occupancy.wrapping_sub(mask) & mask;
+
+            if occupancy == 0 {
+                break;
+            }
+        }
+
+        magic
+    }
+}
+
+pub fn square(board_x: u8, board_y: u8) -> usize {
+    board_y as usize * 8 + board_x as usize
+}
+
+pub fn square_bit(board_x: u8, board_y: u8) -> Bitboard {
+    1 << square(board_x, board_y)
+}
+
+pub fn coordinates(square: usize) -> (u8, u8) {
+    ((square % 8) as u8, (square / 8) as u8)
+}
+
+// Iterates over the squares of a bitboard (from a1 to h8)
+pub fn squares(mut bitboard: Bitboard) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        if bitboard == 0 {
+            return None;
+        }
+
+        let square = bitboard.trailing_zeros() as usize;
+        bitboard &= bitboard - 1;
+
+        Some(square)
+    })
+}
+
+pub fn knight_attacks(square: usize) -> Bitboard {
+    ATTACK_TABLES.knight_attacks[square]
+}
+
+pub fn king_attacks(square: usize) -> Bitboard {
+    ATTACK_TABLES.king_attacks[square]
+}
+
+// Squares attacked by a pawn of the given color
+pub fn pawn_attacks(color: PieceColor, square: usize) -> Bitboard {
+    ATTACK_TABLES.pawn_attacks[color.index()][square]
+}
+
+pub fn bishop_attacks(square: usize, occupancy: Bitboard) -> Bitboard {
+    let tables = &*ATTACK_TABLES;
+
+    tables.sliding_attacks[tables.bishop_magics[square].index(occupancy)]
+}
+
+pub fn rook_attacks(square: usize, occupancy: Bitboard) -> Bitboard {
+    let tables = &*ATTACK_TABLES;
+
+    tables.sliding_attacks[tables.rook_magics[square].index(occupancy)]
+}
+
+fn step_attacks(square: usize, offsets: &[(i8, i8)]) -> Bitboard {
+    let (board_x, board_y) = coordinates(square);
+
+    offsets
+        .iter()
+        .filter_map(|&(x_offset, y_offset)| offset_square(board_x, board_y, x_offset, y_offset))
+        .fold(0, |attacks, (board_x, board_y)| {
+            attacks | square_bit(board_x, board_y)
+        })
+}
+
+// Slow version of the sliding attacks, which is used to fill the attack tables
+fn slide_attacks(square: usize, occupancy: Bitboard, directions: &[(i8, i8)]) -> Bitboard {
+    let mut attacks = 0;
+
+    for &(x_offset, y_offset) in directions {
+        let (mut board_x, mut board_y) = coordinates(square);
+
+        while let Some(next_square) = offset_square(board_x, board_y, x_offset, y_offset) {
+            (board_x, board_y) = next_square;
+            attacks |= square_bit(board_x, board_y);
+
+            if occupancy & square_bit(board_x, board_y) != 0 {
+                break;
+            }
+        }
+    }
+
+    attacks
+}
+
+// The last square of each ray is not relevant, because it is attacked either way
+fn relevant_occupancy_mask(square: usize, directions: &[(i8, i8)]) -> Bitboard {
+    let mut mask = 0;
+
+    for &(x_offset, y_offset) in directions {
+        let (mut board_x, mut board_y) = coordinates(square);
+
+        while let Some(next_square) = offset_square(board_x, board_y, x_offset, y_offset) {
+            if offset_square(next_square.0, next_square.1, x_offset, y_offset).is_none() {
+                break;
+            }
+
+            (board_x, board_y) = next_square;
+            mask |= square_bit(board_x, board_y);
+        }
+    }
+
+    mask
+}
+
+fn offset_square(board_x: u8, board_y: u8, x_offset: i8, y_offset: i8) -> Option<(u8, u8)> {
+    let board_x = board_x as i8 + x_offset;
+    let board_y = board_y as i8 + y_offset;
+
+    if (0..8).contains(&board_x) && (0..8).contains(&board_y) {
+        Some((board_x as u8, board_y as u8))
+    } else {
+        None
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
     bitmap,
     mat4::Mat4,
-    piece::PieceColor,
+    piece::{Piece, PieceColor},
     shader::Shader,
     transformations::{rotate_z, scale, translate},
     vec3::Vec3,
@@ -109,6 +109,81 @@ impl Board {
         }
     }
 
+    pub fn scaled_square_size(&self) -> f32 {
+        Piece::TEXTURE_SIZE as f32 * self.width / Board::TEXTURE_SIZE as f32
+    }
+
+    pub fn scaled_border_size(&self) -> f32 {
+        Board::BORDER_TEXTURE_SIZE as f32 * self.width / Board::TEXTURE_SIZE as f32
+    }
+
+    // Returns the top left corner of a square (without the board rotation)
+    pub fn square_position(&self, board_x: u8, board_y: u8) -> (f32, f32) {
+        let scaled_square_size = self.scaled_square_size();
+        let scaled_border_size = self.scaled_border_size();
+
+        (
+            self.x + scaled_border_size + (board_x as i8 - 7).abs() as f32 * scaled_square_size,
+            self.y + scaled_border_size + (board_y as i8 - 7).abs() as f32 * scaled_square_size,
+        )
+    }
+
+    // Returns the square at a world position (with the board rotation)
+    pub fn square_at(&self, x: f32, y: f32) -> Option<(u8, u8)> {
+        let (x, y) = self.unrotate(x, y);
+
+        // Invert square_position
+        let scaled_square_size = self.scaled_square_size();
+        let scaled_border_size = self.scaled_border_size();
+
+        let column = ((x - self.x - scaled_border_size) / scaled_square_size).floor();
+        let row = ((y - self.y - scaled_border_size) / scaled_square_size).floor();
+
+        if !(0.0..8.0).contains(&column) || !(0.0..8.0).contains(&row) {
+            return None;
+        }
+
+        Some((7 - column as u8, 7 - row as u8))
+    }
+
+    // Converts a world position (with the board rotation) into a world position without the board
+    // rotation
+    pub fn unrotate(&self, x: f32, y: f32) -> (f32, f32) {
+        // Rotate back around board center (counter-clock-wise)
+        let board_center_x = self.x + self.width / 2.0;
+        let board_center_y = self.y + self.height / 2.0;
+
+        let sin = self.rotation.to_radians().sin();
+        let cos = self.rotation.to_radians().cos();
+
+        let x_translation = x - board_center_x;
+        let y_translation = y - board_center_y;
+
+        let x = board_center_x + cos * x_translation + sin * y_translation;
+        let y = board_center_y - sin * x_translation + cos * y_translation;
+
+        (x, y)
+    }
+
+    // Converts a world position without the board rotation into a world position with the board
+    // rotation
+    pub fn rotate(&self, x: f32, y: f32) -> (f32, f32) {
+        // Rotate around board center (clock-wise)
+        let board_center_x = self.x + self.width / 2.0;
+        let board_center_y = self.y + self.height / 2.0;
+
+        let sin = self.rotation.to_radians().sin();
+        let cos = self.rotation.to_radians().cos();
+
+        let x_translation = x - board_center_x;
+        let y_translation = y - board_center_y;
+
+        let x = board_center_x + cos * x_translation - sin * y_translation;
+        let y = board_center_y + sin * x_translation + cos * y_translation;
+
+        (x, y)
+    }
+
     pub fn draw(&self, projection: &Mat4) -> Result<(), Box<dyn Error>> {
         unsafe {
             // Bind vertex buffer object
@@ -170,3 +245,116 @@ impl Board {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Rotations of both points of view and of a board flip in between
+    const ROTATIONS: [f32; 4] = [0.0, 90.0, 180.0, 270.0];
+
+    fn board(rotation: f32) -> Board {
+        Board {
+            x: 100.0,
+            y: 50.0,
+            width: 620.0,
+            height: 620.0,
+            rotation,
+            pov: PieceColor::White,
+        }
+    }
+
+    // Returns a point of a square relative to its top left corner (with the board rotation)
+    fn point(board: &Board, (board_x, board_y): (u8, u8), offset: f32) -> (f32, f32) {
+        let (x, y) = board.square_position(board_x, board_y);
+
+        board.rotate(x + offset, y + offset)
+    }
+
+    fn assert_close((x1, y1): (f32, f32), (x2, y2): (f32, f32)) {
+        assert!(
+            (x1 - x2).abs() < 1e-3 && (y1 - y2).abs() < 1e-3,
+            "({x1}, {y1}) != ({x2}, {y2})"
+        );
+    }
+
+    #[test]
+    fn square_at_inverts_square_position() {
+        for rotation in ROTATIONS {
+            let board = board(rotation);
+            let scaled_square_size = board.scaled_square_size();
+
+            for square in (0..8).flat_map(|board_x| (0..8).map(move |board_y| (board_x, board_y))) {
+                // Center and (just inside) the corners of the square
+                for offset in [0.01, scaled_square_size / 2.0, scaled_square_size - 0.01] {
+                    let (x, y) = point(&board, square, offset);
+
+                    assert_eq!(board.square_at(x, y), Some(square), "{rotation}°");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rotate_inverts_unrotate() {
+        for rotation in [0.0, 37.0, 90.0, 180.0, 270.0] {
+            let board = board(rotation);
+
+            for (x, y) in [(0.0, 0.0), (100.0, 50.0), (410.0, 360.0), (800.0, 123.0)] {
+                let (rotated_x, rotated_y) = board.rotate(x, y);
+                assert_close(board.unrotate(rotated_x, rotated_y), (x, y));
+
+                let (unrotated_x, unrotated_y) = board.unrotate(x, y);
+                assert_close(board.rotate(unrotated_x, unrotated_y), (x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn opposite_points_of_view_mirror_the_squares() {
+        let white = board(0.0);
+        let black = board(180.0);
+        let center = white.scaled_square_size() / 2.0;
+
+        for square in [(0, 0), (4, 0), (7, 7), (2, 5)] {
+            let mirrored = (7 - square.0, 7 - square.1);
+
+            assert_close(
+                point(&black, square, center),
+                point(&white, mirrored, center),
+            );
+        }
+    }
+
+    #[test]
+    fn border_and_outside_are_no_squares() {
+        for rotation in ROTATIONS {
+            let board = board(rotation);
+            let border = board.scaled_border_size();
+            let center_x = board.x + board.width / 2.0;
+            let center_y = board.y + board.height / 2.0;
+
+            for (x, y) in [
+                // Border
+                (board.x + border / 2.0, center_y),
+                (board.x + board.width - border / 2.0, center_y),
+                (center_x, board.y + border / 2.0),
+                (center_x, board.y + board.height - border / 2.0),
+                // Outside of the board
+                (board.x - 1.0, center_y),
+                (board.x + board.width + 1.0, center_y),
+                (center_x, board.y - 1.0),
+                (center_x, board.y + board.height + 1.0),
+                (0.0, 0.0),
+            ] {
+                assert_eq!(board.square_at(x, y), None, "{rotation}° ({x}, {y})");
+            }
+
+            // Just inside the border
+            assert!(board.square_at(board.x + border + 0.01, center_y).is_some());
+            assert!(board
+                .square_at(center_x, board.y + board.height - border - 0.01)
+                .is_some());
+        }
+    }
+}
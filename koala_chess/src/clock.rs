@@ -0,0 +1,454 @@
+use crate::piece::PieceColor;
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    time::Duration,
+};
+
+// Time which is added after each move
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Bonus {
+    None,
+    // Fischer increment: the full amount is added
+    Increment(Duration),
+    // Bronstein delay: the time used for the move is added back (up to the amount)
+    Delay(Duration),
+}
+
+// Part of a time control, e.g. 40 moves in 90 minutes
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct Stage {
+    // None means that the stage lasts for the rest of the game
+    pub moves: Option<u32>,
+    pub time: Duration,
+    pub bonus: Bonus,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct TimeControl {
+    pub stages: Vec<Stage>,
+}
+
+impl TimeControl {
+    // Syntax of the PGN TimeControl tag (in seconds), e.g. "300" (sudden death), "180+2" (Fischer
+    // increment) or "40/5400+30:1800+30" (multiple stages). "300d3" adds a Bronstein delay
+    // The last stage is repeated if it has a number of moves
+    pub fn parse(text: &str) -> Result<TimeControl, Box<dyn Error>> {
+        let invalid = || format!("Invalid time control! ({text})");
+
+        let stages = text
+            .split(':')
+            .map(|stage| {
+                let (moves, rest) = match stage.split_once('/') {
+                    Some((moves, rest)) => {
+                        (Some(moves.parse::<u32>().map_err(|_| invalid())?), rest)
+                    }
+                    None => (None, stage),
+                };
+
+                let (time, bonus) = if let Some((time, increment)) = rest.split_once('+') {
+                    (
+                        time,
+                        Bonus::Increment(parse_seconds(increment).ok_or_else(invalid)?),
+                    )
+                } else if let Some((time, delay)) = rest.split_once('d') {
+                    (
+                        time,
+                        Bonus::Delay(parse_seconds(delay).ok_or_else(invalid)?),
+                    )
+                } else {
+                    (rest, Bonus::None)
+                };
+
+                if moves == Some(0) {
+                    return Err(invalid());
+                }
+
+                Ok(Stage {
+                    moves,
+                    time: parse_seconds(time).ok_or_else(invalid)?,
+                    bonus,
+                })
+            })
+            .collect::<Result<Vec<Stage>, String>>()?;
+
+        // Stages after a stage for the rest of the game would never be reached
+        if stages[..stages.len() - 1]
+            .iter()
+            .any(|stage| stage.moves.is_none())
+        {
+            return Err(invalid().into());
+        }
+
+        Ok(TimeControl { stages })
+    }
+}
+
+impl Display for TimeControl {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for (index, stage) in self.stages.iter().enumerate() {
+            if index > 0 {
+                write!(f, ":")?;
+            }
+
+            if let Some(moves) = stage.moves {
+                write!(f, "{moves}/")?;
+            }
+
+            write!(f, "{}", format_seconds(stage.time))?;
+
+            match stage.bonus {
+                Bonus::None => (),
+                Bonus::Increment(increment) => write!(f, "+{}", format_seconds(increment))?,
+                Bonus::Delay(delay) => write!(f, "d{}", format_seconds(delay))?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Chess clock of both sides. The current time is passed in (see platform::monotonic_time), so
+// that the clock does not depend on a specific time source
+pub struct Clock {
+    pub time_control: TimeControl,
+    // remaining[color] without the running turn
+    remaining: [Duration; 2],
+    // stages[color]
+    stages: [usize; 2],
+    // moves_in_stage[color]
+    moves_in_stage: [u32; 2],
+    // Side whose clock is running and the time its turn has started
+    running: Option<(PieceColor, Duration)>,
+    flagged: Option<PieceColor>,
+}
+
+impl Clock {
+    pub fn new(time_control: TimeControl) -> Clock {
+        let time = time_control.stages[0].time;
+
+        Clock {
+            time_control,
+            remaining: [time; 2],
+            stages: [0; 2],
+            moves_in_stage: [0; 2],
+            running: None,
+            flagged: None,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    // Starts the turn of the given side
+    pub fn start(&mut self, color: PieceColor, now: Duration) {
+        if self.flagged.is_none() {
+            self.running = Some((color, now));
+        }
+    }
+
+    // Stops the clock (e.g. when the game is over)
+    pub fn stop(&mut self, now: Duration) {
+        if let Some((color, _)) = self.running {
+            self.remaining[color.index()] = self.remaining(color, now);
+            self.running = None;
+        }
+    }
+
+    pub fn remaining(&self, color: PieceColor, now: Duration) -> Duration {
+        let remaining = self.remaining[color.index()];
+
+        match self.running {
+            Some((running_color, turn_start)) if running_color == color => {
+                remaining.saturating_sub(now.saturating_sub(turn_start))
+            }
+            _ => remaining,
+        }
+    }
+
+    // Returns the side whose flag has fallen (its time has run out). The clock stops in that case
+    pub fn check_flag(&mut self, now: Duration) -> Option<PieceColor> {
+        if let Some((color, _)) = self.running {
+            if self.remaining(color, now).is_zero() {
+                self.stop(now);
+                self.flagged = Some(color);
+            }
+        }
+
+        self.flagged
+    }
+
+    // Ends the turn of the running side (after it has moved) and starts the turn of the other side
+    pub fn press(&mut self, now: Duration) {
+        let Some((color, turn_start)) = self.running else {
+            return;
+        };
+
+        if self.check_flag(now).is_some() {
+            return;
+        }
+
+        let index = color.index();
+        let stage = self.stage(color);
+        let used = now.saturating_sub(turn_start);
+
+        self.remaining[index] = self.remaining(color, now)
+            + match stage.bonus {
+                Bonus::None => Duration::ZERO,
+                Bonus::Increment(increment) => increment,
+                Bonus::Delay(delay) => used.min(delay),
+            };
+
+        // The time of the next stage is added once the moves of the stage have been played
+        self.moves_in_stage[index] += 1;
+
+        if Some(self.moves_in_stage[index]) == stage.moves {
+            self.stages[index] = (self.stages[index] + 1).min(self.time_control.stages.len() - 1);
+            self.moves_in_stage[index] = 0;
+            self.remaining[index] += self.stage(color).time;
+        }
+
+        self.running = Some((color.opposite(), now));
+    }
+
+    // Time which is added after the next move of the given side (at most in case of a delay)
+    pub fn increment(&self, color: PieceColor) -> Duration {
+        match self.stage(color).bonus {
+            Bonus::None => Duration::ZERO,
+            Bonus::Increment(increment) | Bonus::Delay(increment) => increment,
+        }
+    }
+
+    // Number of moves until the next stage (if there is one)
+    pub fn moves_to_go(&self, color: PieceColor) -> Option<u32> {
+        self.stage(color)
+            .moves
+            .map(|moves| moves - self.moves_in_stage[color.index()])
+    }
+
+    fn stage(&self, color: PieceColor) -> Stage {
+        self.time_control.stages[self.stages[color.index()]]
+    }
+}
+
+// h:mm:ss, m:ss or (below 10 seconds) s.t
+pub fn format_time(time: Duration) -> String {
+    let seconds = time.as_secs();
+
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    } else if seconds >= 10 {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    } else {
+        format!("{}.{}", seconds, time.subsec_millis() / 100)
+    }
+}
+
+fn parse_seconds(text: &str) -> Option<Duration> {
+    // Negative, infinite or too large values are rejected (e.g. "1e30")
+    text.parse::<f32>()
+        .ok()
+        .and_then(|seconds| Duration::try_from_secs_f32(seconds).ok())
+}
+
+fn format_seconds(time: Duration) -> String {
+    if time.subsec_millis() == 0 {
+        time.as_secs().to_string()
+    } else {
+        time.as_secs_f32().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    fn clock(time_control: &str) -> Clock {
+        let mut clock = Clock::new(TimeControl::parse(time_control).unwrap());
+        clock.start(PieceColor::White, Duration::ZERO);
+
+        clock
+    }
+
+    #[test]
+    fn parse_time_controls() {
+        assert_eq!(
+            TimeControl::parse("300").unwrap().stages,
+            [Stage {
+                moves: None,
+                time: secs(300),
+                bonus: Bonus::None,
+            }]
+        );
+        assert_eq!(
+            TimeControl::parse("180+2").unwrap().stages[0].bonus,
+            Bonus::Increment(secs(2))
+        );
+        assert_eq!(
+            TimeControl::parse("300d3").unwrap().stages[0].bonus,
+            Bonus::Delay(secs(3))
+        );
+        assert_eq!(
+            TimeControl::parse("15+0.5").unwrap().stages[0].bonus,
+            Bonus::Increment(Duration::from_millis(500))
+        );
+        assert_eq!(
+            TimeControl::parse("40/5400+30:1800+30").unwrap().stages,
+            [
+                Stage {
+                    moves: Some(40),
+                    time: secs(5400),
+                    bonus: Bonus::Increment(secs(30)),
+                },
+                Stage {
+                    moves: None,
+                    time: secs(1800),
+                    bonus: Bonus::Increment(secs(30)),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn format_time_controls() {
+        for text in [
+            "300",
+            "180+2",
+            "300d3",
+            "15+0.5",
+            "40/5400+30:1800+30",
+            "40/7200",
+        ] {
+            assert_eq!(TimeControl::parse(text).unwrap().to_string(), text);
+        }
+    }
+
+    #[test]
+    fn invalid_time_controls() {
+        for text in [
+            "",
+            "abc",
+            "-5",
+            "inf",
+            "NaN",
+            "1e30",
+            "300+1e30",
+            "300d-1",
+            "0/300",
+            "40/",
+            "300+",
+            "300:40/60",
+        ] {
+            assert!(TimeControl::parse(text).is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn increment() {
+        let mut clock = clock("180+2");
+
+        assert_eq!(clock.remaining(PieceColor::White, secs(10)), secs(170));
+
+        clock.press(secs(10));
+        assert_eq!(clock.remaining(PieceColor::White, secs(20)), secs(172));
+        assert_eq!(clock.remaining(PieceColor::Black, secs(20)), secs(170));
+        assert_eq!(clock.increment(PieceColor::Black), secs(2));
+    }
+
+    #[test]
+    fn delay() {
+        let mut clock = clock("300d3");
+
+        // The time used is added back up to the delay
+        clock.press(secs(2));
+        assert_eq!(clock.remaining(PieceColor::White, secs(2)), secs(300));
+
+        clock.press(secs(12));
+        assert_eq!(clock.remaining(PieceColor::Black, secs(12)), secs(293));
+    }
+
+    #[test]
+    fn stage_transitions() {
+        let mut clock = clock("2/60:30+5");
+
+        assert_eq!(clock.moves_to_go(PieceColor::White), Some(2));
+
+        clock.press(secs(10));
+        clock.press(secs(10));
+        assert_eq!(clock.moves_to_go(PieceColor::White), Some(1));
+        assert_eq!(clock.increment(PieceColor::White), Duration::ZERO);
+
+        // The time of the next stage is added after the second move (without the increment)
+        clock.press(secs(20));
+        assert_eq!(clock.remaining(PieceColor::White, secs(20)), secs(70));
+        assert_eq!(clock.moves_to_go(PieceColor::White), None);
+        assert_eq!(clock.increment(PieceColor::White), secs(5));
+
+        clock.press(secs(20));
+        clock.press(secs(30));
+        assert_eq!(clock.remaining(PieceColor::White, secs(30)), secs(65));
+
+        // Black has reached the next stage with its second move as well
+        assert_eq!(clock.remaining(PieceColor::Black, secs(30)), secs(90));
+        assert_eq!(clock.moves_to_go(PieceColor::Black), None);
+    }
+
+    #[test]
+    fn last_stage_is_repeated() {
+        let mut clock = clock("1/60");
+
+        clock.press(secs(10));
+        assert_eq!(clock.remaining(PieceColor::White, secs(10)), secs(110));
+        assert_eq!(clock.moves_to_go(PieceColor::White), Some(1));
+    }
+
+    #[test]
+    fn flag_fall() {
+        let mut clock = clock("10");
+
+        assert_eq!(clock.check_flag(secs(9)), None);
+        assert_eq!(clock.check_flag(secs(10)), Some(PieceColor::White));
+        assert!(!clock.is_running());
+        assert_eq!(clock.remaining(PieceColor::White, secs(20)), Duration::ZERO);
+
+        // The clock cannot be restarted after a flag fall
+        clock.start(PieceColor::Black, secs(20));
+        assert!(!clock.is_running());
+    }
+
+    #[test]
+    fn flag_falls_on_late_press() {
+        let mut clock = clock("10+5");
+
+        // The increment does not save a side whose time has run out
+        clock.press(secs(11));
+        assert_eq!(clock.check_flag(secs(11)), Some(PieceColor::White));
+        assert_eq!(clock.remaining(PieceColor::Black, secs(11)), secs(10));
+    }
+
+    #[test]
+    fn stop() {
+        let mut clock = clock("60");
+
+        clock.stop(secs(15));
+        assert!(!clock.is_running());
+        assert_eq!(clock.remaining(PieceColor::White, secs(30)), secs(45));
+    }
+
+    #[test]
+    fn format_times() {
+        assert_eq!(format_time(secs(3723)), "1:02:03");
+        assert_eq!(format_time(secs(65)), "1:05");
+        assert_eq!(format_time(Duration::from_millis(9_450)), "9.4");
+    }
+}
@@ -0,0 +1,61 @@
+use std::{error::Error, fs, io::ErrorKind, str::FromStr};
+
+pub const DEFAULT_CONFIG_PATH: &str = "koala_chess.cfg";
+
+// Simple "key = value" configuration file. Empty lines and lines starting with '#' are ignored
+#[derive(Default)]
+pub struct Config {
+    entries: Vec<(String, String)>,
+}
+
+impl Config {
+    // A missing file results in an empty configuration
+    pub fn load(path: &str) -> Result<Config, Box<dyn Error>> {
+        match fs::read_to_string(path) {
+            Ok(text) => Config::parse(&text).map_err(|e| format!("{e} ({path})").into()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(format!("Could not read config file! ({path}) ({e})").into()),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Config, Box<dyn Error>> {
+        let mut entries = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').ok_or_else(|| {
+                format!(
+                    "Invalid config: expected 'key = value' in line {}!",
+                    index + 1
+                )
+            })?;
+
+            entries.push((key.trim().to_string(), value.trim().to_string()));
+        }
+
+        Ok(Config { entries })
+    }
+
+    // Returns the last value of a key (later entries override earlier ones)
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|(entry_key, _)| entry_key == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn get_parsed<T: FromStr>(&self, key: &str) -> Result<Option<T>, Box<dyn Error>> {
+        match self.get(key) {
+            Some(value) => value.parse().map(Some).map_err(|_| {
+                format!("Invalid config: invalid value for '{key}'! ({value})").into()
+            }),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use crate::{
+    bitboard::squares,
+    piece::{PieceColor, PieceKind},
+    rules::Position,
+};
+
+// Piece-square tables from the point of view of the white pieces. The first row is the 8th rank
+// Reference: https://www.chessprogramming.org/Simplified_Evaluation_Function
+#[rustfmt::skip]
+const PAWN_TABLE: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+     50,  50,  50,  50,  50,  50,  50,  50,
+     10,  10,  20,  30,  30,  20,  10,  10,
+      5,   5,  10,  25,  25,  10,   5,   5,
+      0,   0,   0,  20,  20,   0,   0,   0,
+      5,  -5, -10,   0,   0, -10,  -5,   5,
+      5,  10,  10, -20, -20,  10,  10,   5,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+#[rustfmt::skip]
+const KNIGHT_TABLE: [i32; 64] = [
+    -50, -40, -30, -30, -30, -30, -40, -50,
+    -40, -20,   0,   0,   0,   0, -20, -40,
+    -30,   0,  10,  15,  15,  10,   0, -30,
+    -30,   5,  15,  20,  20,  15,   5, -30,
+    -30,   0,  15,  20,  20,  15,   0, -30,
+    -30,   5,  10,  15,  15,  10,   5, -30,
+    -40, -20,   0,   5,   5,   0, -20, -40,
+    -50, -40, -30, -30, -30, -30, -40, -50,
+];
+#[rustfmt::skip]
+const BISHOP_TABLE: [i32; 64] = [
+    -20, -10, -10, -10, -10, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,  10,  10,   5,   0, -10,
+    -10,   5,   5,  10,  10,   5,   5, -10,
+    -10,   0,  10,  10,  10,  10,   0, -10,
+    -10,  10,  10,  10,  10,  10,  10, -10,
+    -10,   5,   0,   0,   0,   0,   5, -10,
+    -20, -10, -10, -10, -10, -10, -10, -20,
+];
+#[rustfmt::skip]
+const ROOK_TABLE: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+      5,  10,  10,  10,  10,  10,  10,   5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+      0,   0,   0,   5,   5,   0,   0,   0,
+];
+#[rustfmt::skip]
+const QUEEN_TABLE: [i32; 64] = [
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,   5,   5,   5,   0, -10,
+     -5,   0,   5,   5,   5,   5,   0,  -5,
+      0,   0,   5,   5,   5,   5,   0,  -5,
+    -10,   5,   5,   5,   5,   5,   0, -10,
+    -10,   0,   5,   0,   0,   0,   0, -10,
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+#[rustfmt::skip]
+const KING_TABLE: [i32; 64] = [
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -20, -30, -30, -40, -40, -30, -30, -20,
+    -10, -20, -20, -20, -20, -20, -20, -10,
+     20,  20,   0,   0,   0,   0,  20,  20,
+     20,  30,  10,   0,   0,  10,  30,  20,
+];
+
+// Weights of the evaluation (in centipawns). They can be tuned without touching the search
+#[derive(Clone, Debug)]
+pub struct Evaluation {
+    pub piece_values: [i32; 6],
+    pub piece_square_tables: [[i32; 64]; 6],
+}
+
+impl Default for Evaluation {
+    fn default() -> Evaluation {
+        Evaluation {
+            piece_values: [100, 320, 330, 500, 900, 20_000],
+            piece_square_tables: [
+                PAWN_TABLE,
+                KNIGHT_TABLE,
+                BISHOP_TABLE,
+                ROOK_TABLE,
+                QUEEN_TABLE,
+                KING_TABLE,
+            ],
+        }
+    }
+}
+
+impl Evaluation {
+    // Returns the score of the position from the point of view of the side to move
+    pub fn evaluate(&self, position: &Position) -> i32 {
+        let mut score = 0;
+
+        for color in [PieceColor::White, PieceColor::Black] {
+            for kind in PieceKind::ALL {
+                for square in squares(position.pieces(color, kind)) {
+                    // The tables are mirrored vertically for the black pieces
+                    let table_index = match color {
+                        PieceColor::White => square ^ 56,
+                        PieceColor::Black => square,
+                    };
+
+                    let piece_score = self.piece_value(kind)
+                        + self.piece_square_tables[kind.index()][table_index];
+
+                    if color == position.side_to_move {
+                        score += piece_score;
+                    } else {
+                        score -= piece_score;
+                    }
+                }
+            }
+        }
+
+        score
+    }
+
+    pub fn piece_value(&self, kind: PieceKind) -> i32 {
+        self.piece_values[kind.index()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen;
+
+    fn evaluate(fen: &str) -> i32 {
+        let fen = fen::parse(fen).unwrap();
+
+        Evaluation::default().evaluate(&Position::new(
+            &fen.pieces,
+            fen.side_to_move,
+            fen.en_passant_target,
+        ))
+    }
+
+    // Swaps the colors: the ranks are mirrored, the pieces change their color and the other side
+    // is to move
+    fn mirror(fen: &str) -> String {
+        let fields: Vec<&str> = fen.split(' ').collect();
+        let piece_placement: Vec<String> = fields[0]
+            .split('/')
+            .rev()
+            .map(|rank| {
+                rank.chars()
+                    .map(|c| {
+                        if c.is_ascii_uppercase() {
+                            c.to_ascii_lowercase()
+                        } else {
+                            c.to_ascii_uppercase()
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let side_to_move = if fields[1] == "w" { "b" } else { "w" };
+
+        format!("{} {side_to_move} - - 0 1", piece_placement.join("/"))
+    }
+
+    #[test]
+    fn evaluation_is_symmetric() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w - - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 0 1",
+            "4k3/8/8/8/8/8/8/3QK3 w - - 0 1",
+        ] {
+            assert_eq!(evaluate(fen), evaluate(&mirror(fen)), "{fen}");
+        }
+
+        // The starting position is balanced
+        assert_eq!(
+            evaluate("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1"),
+            0
+        );
+    }
+
+    #[test]
+    fn evaluation_is_from_the_side_to_move() {
+        let white_to_move = evaluate("4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+        let black_to_move = evaluate("4k3/8/8/8/8/8/8/3QK3 b - - 0 1");
+
+        assert!(white_to_move > 800);
+        assert_eq!(white_to_move, -black_to_move);
+    }
+}
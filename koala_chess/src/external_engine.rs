@@ -0,0 +1,435 @@
+use crate::{rules::Move, uci};
+use logger::*;
+use std::{
+    error::Error,
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+// Time an engine may take to answer "uci", "isready" and "quit"
+const ENGINE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Score {
+    Centipawns(i32),
+    // Moves until mate (negative if the engine is getting mated)
+    Mate(i32),
+}
+
+// Contents of an "info" line (from the point of view of the side to move)
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct EngineInfo {
+    pub depth: Option<u32>,
+    pub score: Option<Score>,
+    pub nodes: Option<u64>,
+    // Principal variation in long algebraic notation (e.g. e2e4)
+    pub pv: Vec<String>,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum EngineEvent {
+    Info(EngineInfo),
+    // Best move in long algebraic notation (e.g. e2e4)
+    BestMove(String),
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum EngineLimit {
+    MoveTime(Duration),
+    // Runs until the search is stopped (analysis)
+    Infinite,
+}
+
+// An external engine (e.g. Stockfish), which is running as a subprocess and speaks UCI
+pub struct ExternalEngine {
+    pub name: String,
+    child: Child,
+    stdin: ChildStdin,
+    line_receiver: Receiver<String>,
+    is_searching: bool,
+    // Number of "bestmove" lines which belong to cancelled searches
+    cancelled_searches: u32,
+}
+
+impl ExternalEngine {
+    pub fn spawn(path: &str) -> Result<ExternalEngine, Box<dyn Error>> {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Could not start engine! ({path}) ({e})"))?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| format!("Could not open engine stdin! ({path})"))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| format!("Could not open engine stdout! ({path})"))?;
+
+        // Read stdout on a separate thread, so that polling never blocks
+        let (line_sender, line_receiver) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("engine stdout".to_string())
+            .spawn(move || {
+                for line in BufReader::new(stdout).lines() {
+                    let Ok(line) = line else {
+                        break;
+                    };
+
+                    if line_sender.send(line).is_err() {
+                        break;
+                    }
+                }
+            })
+            .map_err(|e| format!("Could not spawn engine stdout thread! ({e})"))?;
+
+        let mut engine = ExternalEngine {
+            name: path.to_string(),
+            child,
+            stdin,
+            line_receiver,
+            is_searching: false,
+            cancelled_searches: 0,
+        };
+
+        engine.send("uci")?;
+
+        for line in engine.wait_for("uciok")? {
+            if let Some(name) = line.strip_prefix("id name ") {
+                engine.name = name.trim().to_string();
+            }
+        }
+
+        engine.send("isready")?;
+        engine.wait_for("readyok")?;
+
+        info!("Started engine: {}", engine.name);
+
+        Ok(engine)
+    }
+
+    // Searches the position after the moves (from the starting position given as FEN). A search
+    // which is still running is cancelled first
+    pub fn start(
+        &mut self,
+        fen: &str,
+        moves: &[Move],
+        limit: EngineLimit,
+    ) -> Result<(), Box<dyn Error>> {
+        self.cancel()?;
+
+        let mut position = format!("position fen {fen}");
+
+        if !moves.is_empty() {
+            position.push_str(" moves");
+
+            for r#move in moves {
+                position.push(' ');
+                position.push_str(&uci::format_move(*r#move));
+            }
+        }
+
+        self.send(&position)?;
+
+        match limit {
+            EngineLimit::MoveTime(move_time) => {
+                self.send(&format!("go movetime {}", move_time.as_millis()))?
+            }
+            EngineLimit::Infinite => self.send("go infinite")?,
+        }
+
+        self.is_searching = true;
+
+        Ok(())
+    }
+
+    // Stops the current search and drops its best move
+    pub fn cancel(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.is_searching {
+            self.send("stop")?;
+            self.is_searching = false;
+            self.cancelled_searches += 1;
+        }
+
+        Ok(())
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.is_searching
+    }
+
+    // Returns the events of the current search which have arrived since the last poll
+    // (non-blocking)
+    pub fn poll(&mut self) -> Vec<EngineEvent> {
+        let mut events = Vec::new();
+
+        while let Ok(line) = self.line_receiver.try_recv() {
+            let Some(event) = parse_line(&line) else {
+                continue;
+            };
+
+            // Everything up to the best move of a cancelled search belongs to that search
+            if self.cancelled_searches > 0 {
+                if let EngineEvent::BestMove(_) = event {
+                    self.cancelled_searches -= 1;
+                }
+
+                continue;
+            }
+
+            if let EngineEvent::BestMove(_) = event {
+                self.is_searching = false;
+            }
+
+            events.push(event);
+        }
+
+        events
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), Box<dyn Error>> {
+        debug!("Engine <- {}", command);
+
+        writeln!(self.stdin, "{command}")
+            .and_then(|()| self.stdin.flush())
+            .map_err(|e| format!("Could not send command to engine! ({command}) ({e})").into())
+    }
+
+    // Returns all lines up to (and excluding) the expected line
+    fn wait_for(&mut self, expected_line: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        let deadline = Instant::now() + ENGINE_TIMEOUT;
+        let mut lines = Vec::new();
+
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+
+            match self.line_receiver.recv_timeout(timeout) {
+                Ok(line) if line.trim() == expected_line => return Ok(lines),
+                Ok(line) => lines.push(line),
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(format!("Engine did not answer with '{expected_line}'!").into())
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(format!("Engine quit before answering '{expected_line}'!").into())
+                }
+            }
+        }
+    }
+}
+
+impl Drop for ExternalEngine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+
+        // Give the engine some time to quit on its own
+        let deadline = Instant::now() + ENGINE_TIMEOUT;
+
+        while Instant::now() < deadline {
+            match self.child.try_wait() {
+                Ok(Some(_)) => return,
+                Ok(None) => thread::sleep(Duration::from_millis(10)),
+                Err(_) => break,
+            }
+        }
+
+        if let Err(e) = self.child.kill() {
+            error!("Could not kill engine! ({})", e);
+        }
+
+        let _ = self.child.wait();
+    }
+}
+
+fn parse_line(line: &str) -> Option<EngineEvent> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+
+    match tokens.first() {
+        Some(&"bestmove") => Some(EngineEvent::BestMove(tokens.get(1)?.to_string())),
+        Some(&"info") => Some(EngineEvent::Info(parse_info(&tokens[1..]))),
+        _ => None,
+    }
+}
+
+// info [depth <n>] [score cp <n> | score mate <n>] [nodes <n>] [pv <move> ...] ...
+fn parse_info(tokens: &[&str]) -> EngineInfo {
+    let mut info = EngineInfo::default();
+    let mut index = 0;
+
+    while index < tokens.len() {
+        let value = tokens.get(index + 1);
+
+        match tokens[index] {
+            "depth" => info.depth = value.and_then(|value| value.parse().ok()),
+            "nodes" => info.nodes = value.and_then(|value| value.parse().ok()),
+            "score" => {
+                let score = tokens.get(index + 2).and_then(|score| score.parse().ok());
+
+                info.score = match (value, score) {
+                    (Some(&"cp"), Some(score)) => Some(Score::Centipawns(score)),
+                    (Some(&"mate"), Some(score)) => Some(Score::Mate(score)),
+                    _ => None,
+                };
+
+                index += 1;
+            }
+            // The principal variation is always the last part of the line
+            "pv" => {
+                info.pv = tokens[index + 1..]
+                    .iter()
+                    .map(|token| token.to_string())
+                    .collect();
+
+                break;
+            }
+            _ => {
+                index += 1;
+                continue;
+            }
+        }
+
+        index += 2;
+    }
+
+    info
+}
+
+#[cfg(all(test, target_family = "unix"))]
+mod tests {
+    use super::*;
+    use crate::{
+        fen::STARTING_FEN, game::Game, piece::PieceColor, player::PlayerKind, rules::Move,
+    };
+    use std::{fs, os::unix::fs::PermissionsExt, path::PathBuf};
+
+    // A stand-in engine, which always plays e2e4 (as white) or e7e5 (as black)
+    const STAND_IN_ENGINE: &str = r#"#!/bin/sh
+while read -r line; do
+    case "$line" in
+        uci) echo "id name Stand-in"; echo "id author Koala Chess"; echo "uciok" ;;
+        isready) echo "readyok" ;;
+        "position fen "*" moves "*) moves="yes" ;;
+        "position "*) moves="" ;;
+        go*)
+            echo "info depth 1 seldepth 1 score cp 13 nodes 20 nps 1000 pv e2e4 e7e5"
+            if [ -n "$moves" ]; then echo "bestmove e7e5"; else echo "bestmove e2e4 ponder e7e5"; fi ;;
+        quit) exit 0 ;;
+    esac
+done
+"#;
+
+    fn write_stand_in_engine(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "koala_chess_stand_in_{}_{name}.sh",
+            std::process::id()
+        ));
+
+        fs::write(&path, STAND_IN_ENGINE).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+
+        path
+    }
+
+    fn poll_until_best_move(engine: &mut ExternalEngine) -> Vec<EngineEvent> {
+        let deadline = Instant::now() + ENGINE_TIMEOUT;
+        let mut events = Vec::new();
+
+        while Instant::now() < deadline {
+            events.extend(engine.poll());
+
+            if matches!(events.last(), Some(EngineEvent::BestMove(_))) {
+                return events;
+            }
+
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        panic!("No best move! ({events:?})");
+    }
+
+    #[test]
+    fn handshake_and_search() {
+        let path = write_stand_in_engine("handshake_and_search");
+        let mut engine = ExternalEngine::spawn(path.to_str().unwrap()).unwrap();
+
+        assert_eq!(engine.name, "Stand-in");
+
+        engine
+            .start(
+                STARTING_FEN,
+                &[],
+                EngineLimit::MoveTime(Duration::from_millis(10)),
+            )
+            .unwrap();
+
+        let events = poll_until_best_move(&mut engine);
+
+        assert_eq!(
+            events,
+            vec![
+                EngineEvent::Info(EngineInfo {
+                    depth: Some(1),
+                    score: Some(Score::Centipawns(13)),
+                    nodes: Some(20),
+                    pv: vec!["e2e4".to_string(), "e7e5".to_string()],
+                }),
+                EngineEvent::BestMove("e2e4".to_string()),
+            ]
+        );
+        assert!(!engine.is_searching());
+
+        // The best move of a cancelled search is dropped
+        engine
+            .start(STARTING_FEN, &[], EngineLimit::Infinite)
+            .unwrap();
+        engine
+            .start(
+                STARTING_FEN,
+                &[Move::new(4, 1, 4, 3)],
+                EngineLimit::MoveTime(Duration::from_millis(10)),
+            )
+            .unwrap();
+
+        let events = poll_until_best_move(&mut engine);
+
+        assert_eq!(
+            events.last(),
+            Some(&EngineEvent::BestMove("e7e5".to_string()))
+        );
+
+        drop(engine);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn opponent() {
+        let path = write_stand_in_engine("opponent");
+        let mut game = Game::new();
+
+        game.external_engine = Some(ExternalEngine::spawn(path.to_str().unwrap()).unwrap());
+        game.white_player.kind = PlayerKind::ExternalEngine;
+        game.black_player.kind = PlayerKind::ExternalEngine;
+
+        let deadline = Instant::now() + ENGINE_TIMEOUT;
+
+        while game.history.len() < 2 && Instant::now() < deadline {
+            game.update();
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        assert_eq!(
+            game.history,
+            vec![Move::new(4, 1, 4, 3), Move::new(4, 6, 4, 4)]
+        );
+        assert_eq!(game.side_to_move, PieceColor::White);
+
+        drop(game);
+        fs::remove_file(path).unwrap();
+    }
+}
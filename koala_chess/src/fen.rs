@@ -0,0 +1,541 @@
+use crate::{
+    piece::{Piece, PieceColor, PieceKind},
+    rules::{home_rank, Position},
+};
+use std::error::Error;
+
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+pub struct Fen {
+    pub pieces: Vec<Piece>,
+    pub side_to_move: PieceColor,
+    pub en_passant_target: Option<(u8, u8)>,
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
+}
+
+// Reference: https://www.chessprogramming.org/Forsyth-Edwards_Notation
+pub fn parse(fen: &str) -> Result<Fen, Box<dyn Error>> {
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+
+    // The move counters are optional, because some tools omit them
+    if fields.len() != 4 && fields.len() != 6 {
+        return Err(format!(
+            "Invalid FEN: expected 4 or 6 fields, but got {}! ({fen})",
+            fields.len()
+        )
+        .into());
+    }
+
+    let mut pieces = parse_piece_placement(fields[0])?;
+
+    let side_to_move = match fields[1] {
+        "w" => PieceColor::White,
+        "b" => PieceColor::Black,
+        side_to_move => {
+            return Err(format!(
+                "Invalid FEN: side to move has to be 'w' or 'b', but got '{side_to_move}'!"
+            )
+            .into())
+        }
+    };
+
+    parse_castling_rights(fields[2], &mut pieces)?;
+
+    let en_passant_target = match fields[3] {
+        "-" => None,
+        square => {
+            let (board_x, board_y) = parse_square(square)
+                .ok_or_else(|| format!("Invalid FEN: invalid en passant square '{square}'!"))?;
+
+            // The en passant target is the square which has just been skipped by the opponent's pawn
+            let expected_board_y = match side_to_move {
+                PieceColor::White => 5,
+                PieceColor::Black => 2,
+            };
+
+            if board_y != expected_board_y {
+                return Err(format!(
+                    "Invalid FEN: en passant square '{square}' is not on rank {}!",
+                    expected_board_y + 1
+                )
+                .into());
+            }
+
+            // The pawn has to be in front of the skipped square and the squares it has moved over
+            // have to be empty
+            let (pawn_board_y, origin_board_y) = match side_to_move {
+                PieceColor::White => (board_y - 1, board_y + 1),
+                PieceColor::Black => (board_y + 1, board_y - 1),
+            };
+            let piece_at = |board_y: u8| {
+                pieces
+                    .iter()
+                    .find(|piece| piece.board_x == board_x && piece.board_y == board_y)
+                    .map(|piece| (piece.color, piece.kind))
+            };
+
+            if piece_at(pawn_board_y) != Some((side_to_move.opposite(), PieceKind::Pawn))
+                || piece_at(board_y).is_some()
+                || piece_at(origin_board_y).is_some()
+            {
+                return Err(format!(
+                    "Invalid FEN: en passant square '{square}' requires a {:?} pawn on {} and \
+                     empty squares on {square} and {}!",
+                    side_to_move.opposite(),
+                    format_square(board_x, pawn_board_y),
+                    format_square(board_x, origin_board_y)
+                )
+                .into());
+            }
+
+            Some((board_x, board_y))
+        }
+    };
+
+    let (halfmove_clock, fullmove_number) = if fields.len() == 6 {
+        let halfmove_clock = fields[4]
+            .parse::<u32>()
+            .map_err(|e| format!("Invalid FEN: invalid halfmove clock '{}'! ({e})", fields[4]))?;
+        let fullmove_number = fields[5].parse::<u32>().map_err(|e| {
+            format!(
+                "Invalid FEN: invalid fullmove number '{}'! ({e})",
+                fields[5]
+            )
+        })?;
+
+        if fullmove_number == 0 {
+            return Err("Invalid FEN: fullmove number has to start at 1!".into());
+        }
+
+        (halfmove_clock, fullmove_number)
+    } else {
+        (0, 1)
+    };
+
+    let position = Position::new(&pieces, side_to_move, en_passant_target);
+
+    if position.is_in_check(side_to_move.opposite()) {
+        return Err(format!(
+            "Invalid FEN: the side which is not to move ({:?}) is in check!",
+            side_to_move.opposite()
+        )
+        .into());
+    }
+
+    Ok(Fen {
+        pieces,
+        side_to_move,
+        en_passant_target,
+        halfmove_clock,
+        fullmove_number,
+    })
+}
+
+pub fn format(position: &Position, halfmove_clock: u32, fullmove_number: u32) -> String {
+    let mut piece_placement = String::new();
+
+    for board_y in (0..8).rev() {
+        let mut empty_squares = 0;
+
+        for board_x in 0..8 {
+            match position.piece_at(board_x, board_y) {
+                Some((color, kind)) => {
+                    if empty_squares > 0 {
+                        piece_placement.push_str(&empty_squares.to_string());
+                        empty_squares = 0;
+                    }
+
+                    piece_placement.push(piece_char(color, kind));
+                }
+                None => empty_squares += 1,
+            }
+        }
+
+        if empty_squares > 0 {
+            piece_placement.push_str(&empty_squares.to_string());
+        }
+
+        if board_y > 0 {
+            piece_placement.push('/');
+        }
+    }
+
+    let side_to_move = match position.side_to_move {
+        PieceColor::White => "w",
+        PieceColor::Black => "b",
+    };
+
+    let castling_rights = &position.castling_rights;
+    let mut castling = String::new();
+
+    for (right, c) in [
+        (castling_rights.white_king_side, 'K'),
+        (castling_rights.white_queen_side, 'Q'),
+        (castling_rights.black_king_side, 'k'),
+        (castling_rights.black_queen_side, 'q'),
+    ] {
+        if right {
+            castling.push(c);
+        }
+    }
+
+    if castling.is_empty() {
+        castling.push('-');
+    }
+
+    let en_passant = match position.en_passant_target {
+        Some((board_x, board_y)) => format_square(board_x, board_y),
+        None => "-".to_string(),
+    };
+
+    format!("{piece_placement} {side_to_move} {castling} {en_passant} {halfmove_clock} {fullmove_number}")
+}
+
+pub fn parse_square(square: &str) -> Option<(u8, u8)> {
+    let bytes = square.as_bytes();
+
+    if bytes.len() != 2 || !(b'a'..=b'h').contains(&bytes[0]) || !(b'1'..=b'8').contains(&bytes[1])
+    {
+        return None;
+    }
+
+    Some((bytes[0] - b'a', bytes[1] - b'1'))
+}
+
+pub fn format_square(board_x: u8, board_y: u8) -> String {
+    format!("{}{}", (b'a' + board_x) as char, board_y + 1)
+}
+
+fn more_than_8_squares(board_y: u8, rank: &str) -> Box<dyn Error> {
+    format!(
+        "Invalid FEN: rank {} describes more than 8 squares! ({rank})",
+        board_y + 1
+    )
+    .into()
+}
+
+fn parse_piece_placement(piece_placement: &str) -> Result<Vec<Piece>, Box<dyn Error>> {
+    let ranks: Vec<&str> = piece_placement.split('/').collect();
+
+    if ranks.len() != 8 {
+        return Err(format!(
+            "Invalid FEN: expected 8 ranks, but got {}! ({piece_placement})",
+            ranks.len()
+        )
+        .into());
+    }
+
+    let mut pieces = Vec::new();
+
+    // The piece placement starts with the 8th rank
+    for (rank, board_y) in ranks.iter().zip((0..8).rev()) {
+        let mut board_x = 0;
+
+        for c in rank.chars() {
+            if let Some(empty_squares) = c.to_digit(10) {
+                if !(1..=8).contains(&empty_squares) {
+                    return Err(
+                        format!("Invalid FEN: invalid number of empty squares '{c}'!").into(),
+                    );
+                }
+
+                // Checked before adding, so that long ranks of digits cannot overflow
+                if board_x + empty_squares as u8 > 8 {
+                    return Err(more_than_8_squares(board_y, rank));
+                }
+
+                board_x += empty_squares as u8;
+            } else {
+                let (color, kind) = parse_piece_char(c)
+                    .ok_or_else(|| format!("Invalid FEN: invalid piece '{c}'!"))?;
+
+                if board_x >= 8 {
+                    return Err(more_than_8_squares(board_y, rank));
+                }
+
+                if kind == PieceKind::Pawn && (board_y == 0 || board_y == 7) {
+                    return Err(format!(
+                        "Invalid FEN: pawn on rank {} is not allowed!",
+                        board_y + 1
+                    )
+                    .into());
+                }
+
+                let mut piece = Piece::new(color, kind, board_x, board_y);
+
+                // Kings and rooks regain their castling ability when parsing the castling rights
+                piece.has_moved = kind == PieceKind::King || kind == PieceKind::Rook;
+
+                pieces.push(piece);
+                board_x += 1;
+            }
+        }
+
+        if board_x != 8 {
+            return Err(format!(
+                "Invalid FEN: rank {} does not describe exactly 8 squares! ({rank})",
+                board_y + 1
+            )
+            .into());
+        }
+    }
+
+    for color in [PieceColor::White, PieceColor::Black] {
+        let kings = pieces
+            .iter()
+            .filter(|piece| piece.color == color && piece.kind == PieceKind::King)
+            .count();
+
+        if kings != 1 {
+            return Err(format!("Invalid FEN: expected 1 {color:?} king, but got {kings}!").into());
+        }
+    }
+
+    Ok(pieces)
+}
+
+fn parse_castling_rights(castling: &str, pieces: &mut [Piece]) -> Result<(), Box<dyn Error>> {
+    if castling == "-" {
+        return Ok(());
+    }
+
+    for c in castling.chars() {
+        let (color, rook_board_x) = match c {
+            'K' => (PieceColor::White, 7),
+            'Q' => (PieceColor::White, 0),
+            'k' => (PieceColor::Black, 7),
+            'q' => (PieceColor::Black, 0),
+            _ => return Err(format!("Invalid FEN: invalid castling right '{c}'!").into()),
+        };
+
+        let board_y = home_rank(color);
+
+        for (kind, board_x) in [(PieceKind::King, 4), (PieceKind::Rook, rook_board_x)] {
+            let piece = pieces
+                .iter_mut()
+                .find(|piece| {
+                    piece.color == color
+                        && piece.kind == kind
+                        && piece.board_x == board_x
+                        && piece.board_y == board_y
+                })
+                .ok_or_else(|| {
+                    format!(
+                        "Invalid FEN: castling right '{c}' requires a {color:?} {kind:?} on {}!",
+                        format_square(board_x, board_y)
+                    )
+                })?;
+
+            piece.has_moved = false;
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_piece_char(c: char) -> Option<(PieceColor, PieceKind)> {
+    let color = if c.is_ascii_uppercase() {
+        PieceColor::White
+    } else {
+        PieceColor::Black
+    };
+
+    let kind = match c.to_ascii_lowercase() {
+        'p' => PieceKind::Pawn,
+        'n' => PieceKind::Knight,
+        'b' => PieceKind::Bishop,
+        'r' => PieceKind::Rook,
+        'q' => PieceKind::Queen,
+        'k' => PieceKind::King,
+        _ => return None,
+    };
+
+    Some((color, kind))
+}
+
+fn piece_char(color: PieceColor, kind: PieceKind) -> char {
+    let c = match kind {
+        PieceKind::Pawn => 'p',
+        PieceKind::Knight => 'n',
+        PieceKind::Bishop => 'b',
+        PieceKind::Rook => 'r',
+        PieceKind::Queen => 'q',
+        PieceKind::King => 'k',
+    };
+
+    match color {
+        PieceColor::White => c.to_ascii_uppercase(),
+        PieceColor::Black => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(fen: &str) -> String {
+        let parsed = parse(fen).unwrap();
+        let position = Position::new(
+            &parsed.pieces,
+            parsed.side_to_move,
+            parsed.en_passant_target,
+        );
+
+        format(&position, parsed.halfmove_clock, parsed.fullmove_number)
+    }
+
+    fn assert_error(fen: &str, expected_error: &str) {
+        let error = match parse(fen) {
+            Ok(_) => panic!("'{fen}' has been parsed"),
+            Err(e) => e.to_string(),
+        };
+
+        assert!(
+            error.contains(expected_error),
+            "'{error}' does not contain '{expected_error}'"
+        );
+    }
+
+    #[test]
+    fn round_trip_keeps_the_fen() {
+        for fen in [
+            STARTING_FEN,
+            // Kiwipete
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP1PPPP/RNBQKBNR b Kq d3 0 2",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 42 97",
+        ] {
+            assert_eq!(round_trip(fen), fen);
+        }
+    }
+
+    #[test]
+    fn move_counters_are_optional() {
+        assert_eq!(
+            round_trip("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -"),
+            STARTING_FEN
+        );
+    }
+
+    #[test]
+    fn invalid_fields() {
+        assert_error("8/8/8/8/8/8/8/8 w", "expected 4 or 6 fields, but got 2");
+        assert_error(
+            "4k3/8/8/8/8/8/8/4K3 x - - 0 1",
+            "side to move has to be 'w' or 'b', but got 'x'",
+        );
+        assert_error(
+            "4k3/8/8/8/8/8/8/4K3 w - - x 1",
+            "invalid halfmove clock 'x'",
+        );
+        assert_error(
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 x",
+            "invalid fullmove number 'x'",
+        );
+        assert_error(
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 0",
+            "fullmove number has to start at 1",
+        );
+    }
+
+    #[test]
+    fn invalid_piece_placement() {
+        assert_error("4k3/8/8/8/8/8/4K3 w - - 0 1", "expected 8 ranks, but got 7");
+        assert_error(
+            "4k3/8/8/8/8/8/8/4K2 w - - 0 1",
+            "rank 1 does not describe exactly 8 squares",
+        );
+        assert_error(
+            "4k3/8/8/8/8/8/8/4K3R w - - 0 1",
+            "rank 1 describes more than 8 squares",
+        );
+        assert_error(
+            "4k3/8/8/8/8/8/8/4K3/ w - - 0 1",
+            "expected 8 ranks, but got 9",
+        );
+        assert_error(
+            "4k3/8/8/8/8/8/8/4K35 w - - 0 1",
+            "rank 1 describes more than 8 squares",
+        );
+        // Ranks of digits only (32 eights would overflow the file counter)
+        assert_error(
+            "4k3/8/8/8/72/8/8/4K3 w - - 0 1",
+            "rank 4 describes more than 8 squares",
+        );
+        assert_error(
+            &format!("4k3/{}/8/8/8/8/8/4K3 w - - 0 1", "8".repeat(32)),
+            "rank 7 describes more than 8 squares",
+        );
+        assert_error(
+            "4k3/8/8/8/8/8/8/4K30 w - - 0 1",
+            "invalid number of empty squares '0'",
+        );
+        assert_error("4k3/8/8/8/8/8/8/4K2X w - - 0 1", "invalid piece 'X'");
+        assert_error(
+            "4k2P/8/8/8/8/8/8/4K3 w - - 0 1",
+            "pawn on rank 8 is not allowed",
+        );
+        assert_error(
+            "8/8/8/8/8/8/8/4K3 w - - 0 1",
+            "expected 1 Black king, but got 0",
+        );
+        assert_error(
+            "4k3/8/8/8/8/8/8/3KK3 w - - 0 1",
+            "expected 1 White king, but got 2",
+        );
+        assert_error(
+            "4k2R/8/8/8/8/8/8/4K3 w - - 0 1",
+            "the side which is not to move (Black) is in check",
+        );
+    }
+
+    #[test]
+    fn invalid_castling_rights() {
+        assert_error(
+            "4k3/8/8/8/8/8/8/4K2R w X - 0 1",
+            "invalid castling right 'X'",
+        );
+        assert_error(
+            "4k3/8/8/8/8/8/8/4K3 w K - 0 1",
+            "castling right 'K' requires a White Rook on h1",
+        );
+        assert_error(
+            "r2k4/8/8/8/8/8/8/4K3 w q - 0 1",
+            "castling right 'q' requires a Black King on e8",
+        );
+    }
+
+    #[test]
+    fn invalid_en_passant_square() {
+        assert_error(
+            "4k3/8/8/3Pp3/8/8/8/4K3 w - e9 0 1",
+            "invalid en passant square 'e9'",
+        );
+        assert_error(
+            "4k3/8/8/3Pp3/8/8/8/4K3 w - e3 0 1",
+            "en passant square 'e3' is not on rank 6",
+        );
+
+        // There is no pawn which could have skipped the square
+        assert_error(
+            "4k3/8/8/3P4/8/8/8/4K3 w - e6 0 1",
+            "en passant square 'e6' requires a Black pawn on e5 and empty squares on e6 and e7",
+        );
+
+        // The pawn could not have moved over an occupied square
+        assert_error(
+            "4k3/4n3/8/3Pp3/8/8/8/4K3 w - e6 0 1",
+            "requires a Black pawn on e5",
+        );
+        assert_error(
+            "4k3/8/4n3/3Pp3/8/8/8/4K3 w - e6 0 1",
+            "requires a Black pawn on e5",
+        );
+        assert_error(
+            "4k3/8/8/8/3pP3/8/8/4K3 b - d3 0 1",
+            "requires a White pawn on d4",
+        );
+    }
+}
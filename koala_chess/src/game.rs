@@ -1,22 +1,139 @@
 use crate::{
+    animation::{self, lerp, Animation, CaptureAnimation, MoveAnimation, RotationAnimation},
     board::Board,
+    clock::{format_time, Clock},
+    evaluation::Evaluation,
+    external_engine::{EngineEvent, EngineInfo, EngineLimit, ExternalEngine},
+    fen::{self, Fen, STARTING_FEN},
+    game_result::{claimable_draw, determine_result, timeout_result, GameResult},
+    input::{InputEvent, Key, MouseButton},
+    key_bindings::{self, Action, KeyBindings},
+    mat4::Mat4,
+    overlay::{Highlight, HighlightKind},
+    pgn,
     piece::{Piece, PieceColor, PieceKind},
-    projections::orthogonal_projection,
+    platform,
+    player::{Player, PlayerKind},
+    projections::{inverse_orthogonal_projection, orthogonal_projection},
+    rules::{castling_rook_files, Move, Position},
+    san,
+    search::{time_for_move, SearchLimits, MAX_PLY},
+    search_service::SearchService,
     shader,
+    text::Text,
+    transposition_table, uci,
+    vec4::Vec4,
 };
 use logger::*;
-use std::error::Error;
+use std::{error::Error, time::Duration};
+
+// Time the engines may think about a move (without a clock)
+const ENGINE_TIME_LIMIT: Duration = Duration::from_secs(1);
+
+// Heights of the texts around the board (in world units)
+const COORDINATE_TEXT_HEIGHT: f32 = 20.0;
+const STATUS_TEXT_HEIGHT: f32 = 20.0;
+const CLOCK_TEXT_HEIGHT: f32 = 28.0;
+const MOVE_LIST_TEXT_HEIGHT: f32 = 20.0;
+// Distance between the board and the texts
+const TEXT_MARGIN: f32 = 6.0;
+
+// Duration of a board flip (unless the animations are disabled)
+const BOARD_FLIP_DURATION: Duration = Duration::from_millis(500);
 
 pub struct Game {
+    pub window_width: i32,
+    pub window_height: i32,
+    pub cursor_x: i32,
+    pub cursor_y: i32,
     pub aspect_ratio: f32,
     pub world_width: f32,
     pub world_height: f32,
     pub board: Board,
     pub pieces: Vec<Piece>,
+    pub side_to_move: PieceColor,
+    pub en_passant_target: Option<(u8, u8)>,
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
+    pub position_hashes: Vec<u64>,
+    pub result: GameResult,
+    pub starting_fen: String,
+    pub history: Vec<Move>,
+    pub selected_square: Option<(u8, u8)>,
+    pub white_player: Player,
+    pub black_player: Player,
+    pub evaluation: Evaluation,
+    // None means that the game is played without time control
+    pub clock: Option<Clock>,
+    drag: Option<Drag>,
+    // Created when the engine has to move for the first time
+    search_service: Option<SearchService>,
+    // Size of the transposition table of the engine
+    pub hash_size_mb: usize,
+    // Opponent or analysis backend (see PlayerKind::ExternalEngine and analysis_enabled)
+    pub external_engine: Option<ExternalEngine>,
+    pub analysis_enabled: bool,
+    // Latest info of the external engine
+    pub analysis: Option<EngineInfo>,
+    // Number of plies of the position which the external engine is searching
+    external_engine_plies: Option<usize>,
+    // Duration of the piece movements (zero disables the animations)
+    pub animation_duration: Duration,
+    move_animations: Vec<MoveAnimation>,
+    capture_animations: Vec<CaptureAnimation>,
+    board_flip: Option<RotationAnimation>,
+    // Whether the board is flipped to the side to move after each move of a hot-seat game
+    pub auto_flip: bool,
+    // Moves which have been taken back (the next one is the last)
+    future: Vec<Move>,
+    // States after each ply of the current line (including the moves which have been taken back)
+    snapshots: Vec<Snapshot>,
+    // Legal moves of the current position, which are needed for every frame (see highlights)
+    legal_moves: Vec<Move>,
+    pub key_bindings: KeyBindings,
+    // Requested by the key bindings and applied by the platform layer
+    pub fullscreen: bool,
+    pub quit: bool,
+    // Square of the keyboard cursor (shown once the arrow keys have been used)
+    pub cursor_square: Option<(u8, u8)>,
+    // Move which is being typed (SAN or coordinate notation)
+    pub move_text: String,
+}
+
+// State of the game after a ply, which is restored when navigating through the history. The pieces
+// contain the castling rights (see Piece::has_moved)
+#[derive(Clone)]
+struct Snapshot {
+    pieces: Vec<Piece>,
+    side_to_move: PieceColor,
+    en_passant_target: Option<(u8, u8)>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    hash: u64,
+    // The move which has led to the position in SAN (empty for the starting position)
+    san: String,
+}
+
+// A piece which is being dragged with the mouse
+#[derive(Copy, Clone)]
+struct Drag {
+    board_x: u8,
+    board_y: u8,
+    // Offset of the cursor to the top left corner of the piece (without the board rotation)
+    offset_x: f32,
+    offset_y: f32,
+    // Whether the piece has already been selected before the drag started
+    was_selected: bool,
 }
 
 impl Game {
     pub fn new() -> Game {
+        Game::from_fen(STARTING_FEN).unwrap_or_else(|e| fatal!("{}", e))
+    }
+
+    pub fn from_fen(fen: &str) -> Result<Game, Box<dyn Error>> {
+        let fen = fen::parse(fen)?;
+
         let board = Board {
             x: 0.0,
             y: 0.0,
@@ -26,80 +143,1212 @@ impl Game {
             pov: PieceColor::Black,
         };
 
-        let mut pieces: Vec<Piece> = Vec::new();
-
-        // Create pieces
-        for board_x in 0..8 {
-            #[rustfmt::skip]
-            let white_pawn = Piece::new(PieceColor::White, PieceKind::Pawn, board_x, 1);
-            #[rustfmt::skip]
-            let black_pawn = Piece::new(PieceColor::Black, PieceKind::Pawn, board_x, 6);
-
-            pieces.push(white_pawn);
-            pieces.push(black_pawn);
-        }
-
-        #[rustfmt::skip]
-        let left_white_rook = Piece::new(PieceColor::White, PieceKind::Rook, 0, 0);
-        #[rustfmt::skip]
-        let right_white_rook = Piece::new(PieceColor::White, PieceKind::Rook, 7, 0);
-        #[rustfmt::skip]
-        let left_black_rook = Piece::new(PieceColor::Black, PieceKind::Rook, 0, 7);
-        #[rustfmt::skip]
-        let right_black_rook = Piece::new(PieceColor::Black, PieceKind::Rook, 7, 7);
-
-        #[rustfmt::skip]
-        let left_white_knight = Piece::new(PieceColor::White, PieceKind::Knight, 1, 0);
-        #[rustfmt::skip]
-        let right_white_knight = Piece::new(PieceColor::White, PieceKind::Knight, 6, 0);
-        #[rustfmt::skip]
-        let left_black_knight = Piece::new(PieceColor::Black, PieceKind::Knight, 1, 7);
-        #[rustfmt::skip]
-        let right_black_knight = Piece::new(PieceColor::Black, PieceKind::Knight, 6, 7);
-
-        #[rustfmt::skip]
-        let left_white_bishop = Piece::new(PieceColor::White, PieceKind::Bishop, 2, 0);
-        #[rustfmt::skip]
-        let right_white_bishop = Piece::new(PieceColor::White, PieceKind::Bishop, 5, 0);
-        #[rustfmt::skip]
-        let left_black_bishop = Piece::new(PieceColor::Black, PieceKind::Bishop, 2, 7);
-        #[rustfmt::skip]
-        let right_black_bishop = Piece::new(PieceColor::Black, PieceKind::Bishop, 5, 7);
-
-        #[rustfmt::skip]
-        let white_queen = Piece::new(PieceColor::White, PieceKind::Queen, 3, 0);
-        #[rustfmt::skip]
-        let black_queen = Piece::new(PieceColor::Black, PieceKind::Queen, 3, 7);
-
-        #[rustfmt::skip]
-        let white_king = Piece::new(PieceColor::White, PieceKind::King, 4, 0);
-        #[rustfmt::skip]
-        let black_king = Piece::new(PieceColor::Black, PieceKind::King, 4, 7);
-
-        pieces.push(left_white_rook);
-        pieces.push(right_white_rook);
-        pieces.push(left_black_rook);
-        pieces.push(right_black_rook);
-        pieces.push(left_white_knight);
-        pieces.push(right_white_knight);
-        pieces.push(left_black_knight);
-        pieces.push(right_black_knight);
-        pieces.push(left_white_bishop);
-        pieces.push(right_white_bishop);
-        pieces.push(left_black_bishop);
-        pieces.push(right_black_bishop);
-        pieces.push(white_queen);
-        pieces.push(black_queen);
-        pieces.push(white_king);
-        pieces.push(black_king);
-
-        Game {
+        let mut game = Game {
+            window_width: 0,
+            window_height: 0,
+            cursor_x: 0,
+            cursor_y: 0,
             aspect_ratio: 0.0,
             world_width: 800.0,
             world_height: 800.0,
             board,
-            pieces,
+            pieces: fen.pieces,
+            side_to_move: fen.side_to_move,
+            en_passant_target: fen.en_passant_target,
+            halfmove_clock: fen.halfmove_clock,
+            fullmove_number: fen.fullmove_number,
+            position_hashes: Vec::new(),
+            result: GameResult::Ongoing,
+            starting_fen: String::new(),
+            history: Vec::new(),
+            selected_square: None,
+            white_player: Player::new(PieceColor::White, PlayerKind::Human),
+            black_player: Player::new(PieceColor::Black, PlayerKind::Engine),
+            evaluation: Evaluation::default(),
+            clock: None,
+            drag: None,
+            search_service: None,
+            hash_size_mb: transposition_table::DEFAULT_SIZE_MB,
+            external_engine: None,
+            analysis_enabled: false,
+            analysis: None,
+            external_engine_plies: None,
+            animation_duration: animation::DEFAULT_DURATION,
+            move_animations: Vec::new(),
+            capture_animations: Vec::new(),
+            board_flip: None,
+            auto_flip: false,
+            future: Vec::new(),
+            snapshots: Vec::new(),
+            legal_moves: Vec::new(),
+            key_bindings: KeyBindings::default(),
+            fullscreen: false,
+            quit: false,
+            cursor_square: None,
+            move_text: String::new(),
+        };
+
+        game.starting_fen = game.to_fen();
+
+        let position = game.position();
+        game.position_hashes.push(position.hash());
+        game.result = determine_result(&position, game.halfmove_clock, &game.position_hashes);
+        game.legal_moves = position.legal_moves();
+        game.snapshots.push(game.snapshot(String::new()));
+
+        Ok(game)
+    }
+
+    pub fn to_fen(&self) -> String {
+        fen::format(&self.position(), self.halfmove_clock, self.fullmove_number)
+    }
+
+    pub fn position(&self) -> Position {
+        Position::new(&self.pieces, self.side_to_move, self.en_passant_target)
+    }
+
+    pub fn legal_moves(&self) -> &[Move] {
+        &self.legal_moves
+    }
+
+    pub fn make_move(&mut self, r#move: Move) -> Result<(), Box<dyn Error>> {
+        // The time might have run out since the last update
+        self.update_clock();
+
+        if self.result.is_over() {
+            return Err(format!("The game is already over! ({})", self.result).into());
+        }
+
+        if !self.legal_moves.contains(&r#move) {
+            return Err(format!("Illegal move! ({:?})", r#move).into());
+        }
+
+        let mut position = self.position();
+        let san = san::format(&position, r#move);
+
+        info!("Move: {}", san);
+
+        let is_irreversible = is_irreversible(&position, r#move);
+
+        self.start_animations(&position, r#move);
+
+        // The move is made on the position, which the pieces are rebuilt from
+        position.make_move(r#move);
+        self.apply_position(&position, is_irreversible);
+        self.legal_moves = position.legal_moves();
+
+        self.history.push(r#move);
+        self.position_hashes.push(position.hash());
+
+        // Replaying the next move of the line keeps the rest of it, any other move replaces it
+        if self.future.last() == Some(&r#move) {
+            self.future.pop();
+        } else {
+            self.future.clear();
+            self.snapshots.truncate(self.history.len());
+            self.snapshots.push(self.snapshot(san));
+        }
+
+        self.result = determine_result(&position, self.halfmove_clock, &self.position_hashes);
+
+        if self.result.is_over() {
+            info!("Game over: {}", self.result);
+        }
+
+        if let Some(clock) = self.clock.as_mut() {
+            let now = platform::monotonic_time();
+            clock.press(now);
+
+            info!(
+                "Clock: White: {} / Black: {}",
+                format_time(clock.remaining(PieceColor::White, now)),
+                format_time(clock.remaining(PieceColor::Black, now))
+            );
+        }
+
+        self.update_clock();
+
+        if self.auto_flip && self.is_hot_seat() {
+            self.set_pov(self.side_to_move);
+        }
+
+        // An engine does not move while the line can be replayed, so the rest of the line is
+        // replayed when its next move is made against the engine
+        if !self.future.is_empty() && self.player(self.side_to_move).kind != PlayerKind::Human {
+            self.jump_to_ply(self.plies())?;
+        }
+
+        Ok(())
+    }
+
+    // Takes over the position after a move and updates the move counters
+    fn apply_position(&mut self, position: &Position, is_irreversible: bool) {
+        self.pieces = position.to_pieces();
+        self.en_passant_target = position.en_passant_target;
+
+        if self.side_to_move == PieceColor::Black {
+            self.fullmove_number += 1;
+        }
+
+        self.side_to_move = position.side_to_move;
+
+        // Irreversible moves reset the clock of the fifty-move rule
+        if is_irreversible {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+    }
+
+    // Starts a new game from the starting position (with the same players and time control)
+    pub fn new_game(&mut self) {
+        info!("New game");
+
+        let fen = fen::parse(STARTING_FEN).unwrap_or_else(|e| fatal!("{}", e));
+
+        self.starting_fen = STARTING_FEN.to_string();
+        self.history.clear();
+        self.future.clear();
+        self.reset_position(fen);
+
+        if let Some(clock) = &self.clock {
+            self.clock = Some(Clock::new(clock.time_control.clone()));
+        }
+
+        if let Some(search_service) = &self.search_service {
+            search_service.clear_hash();
+        }
+
+        if self.auto_flip && self.is_hot_seat() {
+            self.set_pov(self.side_to_move);
+        }
+    }
+
+    // Takes back moves until a human is to move
+    pub fn undo(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut ply = self.history.len();
+        let mut color = self.side_to_move;
+
+        while ply > 0 {
+            ply -= 1;
+            color = color.opposite();
+
+            if self.player(color).kind == PlayerKind::Human {
+                break;
+            }
+        }
+
+        self.jump_to_ply(ply)
+    }
+
+    // Replays the moves which have been taken back until a human is to move again
+    pub fn redo(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut ply = self.history.len();
+        let mut color = self.side_to_move;
+
+        while ply < self.plies() {
+            ply += 1;
+            color = color.opposite();
+
+            if self.player(color).kind == PlayerKind::Human {
+                break;
+            }
+        }
+
+        self.jump_to_ply(ply)
+    }
+
+    pub fn step_back(&mut self) -> Result<(), Box<dyn Error>> {
+        self.jump_to_ply(self.history.len().saturating_sub(1))
+    }
+
+    // Replays the next move which has been taken back (animated)
+    pub fn step_forward(&mut self) -> Result<(), Box<dyn Error>> {
+        let Some(r#move) = self.future.last().copied() else {
+            return Ok(());
+        };
+
+        let position = self.position();
+
+        self.jump_to_ply(self.history.len() + 1)?;
+        self.start_animations(&position, r#move);
+
+        Ok(())
+    }
+
+    // Number of plies of the current line (including the moves which have been taken back)
+    pub fn plies(&self) -> usize {
+        self.history.len() + self.future.len()
+    }
+
+    // Moves of the current line (including the moves which have been taken back)
+    pub fn line(&self) -> impl Iterator<Item = &Move> {
+        self.history.iter().chain(self.future.iter().rev())
+    }
+
+    // Result at the end of the current line
+    pub fn line_result(&self) -> GameResult {
+        if self.future.is_empty() {
+            return self.result;
+        }
+
+        let snapshot = &self.snapshots[self.plies()];
+        let position = Position::new(
+            &snapshot.pieces,
+            snapshot.side_to_move,
+            snapshot.en_passant_target,
+        );
+        let position_hashes: Vec<u64> = self
+            .snapshots
+            .iter()
+            .map(|snapshot| snapshot.hash)
+            .collect();
+
+        determine_result(&position, snapshot.halfmove_clock, &position_hashes)
+    }
+
+    // Goes to the position after the given number of plies of the current line. The moves after it
+    // are kept, so that they can be replayed until another move is made
+    pub fn jump_to_ply(&mut self, ply: usize) -> Result<(), Box<dyn Error>> {
+        let plies = self.plies();
+
+        if ply > plies {
+            return Err(format!("The game does not have this ply! ({ply}/{plies})").into());
+        }
+
+        if ply == self.history.len() {
+            return Ok(());
+        }
+
+        if ply < self.history.len() {
+            let taken_back = self.history.split_off(ply);
+            self.future.extend(taken_back.into_iter().rev());
+        } else {
+            let replayed = self.future.split_off(plies - ply);
+            self.history.extend(replayed.into_iter().rev());
+        }
+
+        info!("Ply: {}/{}", ply, plies);
+
+        self.restore(ply);
+
+        if self.auto_flip && self.is_hot_seat() {
+            self.set_pov(self.side_to_move);
+        }
+
+        Ok(())
+    }
+
+    // Returns the state of the current position, which the given move has led to
+    fn snapshot(&self, san: String) -> Snapshot {
+        Snapshot {
+            pieces: self.pieces.clone(),
+            side_to_move: self.side_to_move,
+            en_passant_target: self.en_passant_target,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            hash: self.position().hash(),
+            san,
+        }
+    }
+
+    // Restores the position after the given number of plies of the current line
+    fn restore(&mut self, ply: usize) {
+        let snapshot = self.snapshots[ply].clone();
+
+        self.pieces = snapshot.pieces;
+        self.side_to_move = snapshot.side_to_move;
+        self.en_passant_target = snapshot.en_passant_target;
+        self.halfmove_clock = snapshot.halfmove_clock;
+        self.fullmove_number = snapshot.fullmove_number;
+        self.position_hashes = self.snapshots[..=ply]
+            .iter()
+            .map(|snapshot| snapshot.hash)
+            .collect();
+        self.result =
+            determine_result(&self.position(), self.halfmove_clock, &self.position_hashes);
+        self.legal_moves = self.position().legal_moves();
+
+        self.interrupt();
+    }
+
+    // Sets up a position without moves
+    fn reset_position(&mut self, fen: Fen) {
+        self.pieces = fen.pieces;
+        self.side_to_move = fen.side_to_move;
+        self.en_passant_target = fen.en_passant_target;
+        self.halfmove_clock = fen.halfmove_clock;
+        self.fullmove_number = fen.fullmove_number;
+        self.snapshots = vec![self.snapshot(String::new())];
+        self.restore(0);
+    }
+
+    // Drops the searches, animations and the selection of the previous position
+    fn interrupt(&mut self) {
+        if let Some(search_service) = self.search_service.as_mut() {
+            search_service.cancel();
+        }
+
+        if let Some(external_engine) = self.external_engine.as_mut() {
+            if let Err(e) = external_engine.cancel() {
+                error!("{}", e);
+            }
+        }
+
+        self.external_engine_plies = None;
+        self.analysis = None;
+        self.move_animations.clear();
+        self.capture_animations.clear();
+        self.selected_square = None;
+        self.drag = None;
+        self.move_text.clear();
+    }
+
+    // Performs the action which is bound to a key
+    fn perform_action(&mut self, action: Action) {
+        let navigates = matches!(
+            action,
+            Action::Undo
+                | Action::Redo
+                | Action::StepBack
+                | Action::StepForward
+                | Action::GoToStart
+                | Action::GoToEnd
+        );
+
+        // Taking back moves would give a player additional time
+        if navigates && self.clock.is_some() {
+            info!("Moves cannot be taken back in games with a clock!");
+            return;
+        }
+
+        let result = match action {
+            Action::FlipBoard => {
+                self.flip_board();
+                Ok(())
+            }
+            Action::Undo => self.undo(),
+            Action::ClaimDraw => self.claim_draw(),
+            Action::Redo => self.redo(),
+            Action::NewGame => {
+                self.new_game();
+                Ok(())
+            }
+            Action::StepBack => self.step_back(),
+            Action::StepForward => self.step_forward(),
+            Action::GoToStart => self.jump_to_ply(0),
+            Action::GoToEnd => self.jump_to_ply(self.plies()),
+            Action::ToggleFullscreen => {
+                self.fullscreen = !self.fullscreen;
+                Ok(())
+            }
+            Action::Quit => {
+                self.quit = true;
+                Ok(())
+            }
+        };
+
+        if let Err(e) = result {
+            error!("{}", e);
+        }
+    }
+
+    // Slides the moved pieces to their new squares and fades out the captured piece. The animations
+    // of the previous move are finished
+    fn start_animations(&mut self, position: &Position, r#move: Move) {
+        let animation = Animation::new(self.animation_duration);
+        let (from_y, to_x) = (r#move.from_y, r#move.to_x);
+
+        self.move_animations = vec![MoveAnimation {
+            from: (r#move.from_x, from_y),
+            to: (to_x, r#move.to_y),
+            animation,
+        }];
+
+        if position.is_castling(r#move) {
+            let (rook_from_x, rook_to_x) = castling_rook_files(to_x);
+
+            self.move_animations.push(MoveAnimation {
+                from: (rook_from_x, from_y),
+                to: (rook_to_x, from_y),
+                animation,
+            });
+        }
+
+        // The pawn which is captured en passant is next to the destination
+        let captured_square = if position.is_en_passant(r#move) {
+            (to_x, from_y)
+        } else {
+            (to_x, r#move.to_y)
+        };
+
+        self.capture_animations = position
+            .piece_at(captured_square.0, captured_square.1)
+            .map(|(color, kind)| CaptureAnimation {
+                piece: Piece::new(color, kind, captured_square.0, captured_square.1),
+                animation,
+            })
+            .into_iter()
+            .collect();
+    }
+
+    // Ends the game with a draw by the fifty-move rule or threefold repetition (if possible)
+    pub fn claim_draw(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.result.is_over() {
+            return Err(format!("The game is already over! ({})", self.result).into());
+        }
+
+        let reason = claimable_draw(self.halfmove_clock, &self.position_hashes)
+            .ok_or("A draw cannot be claimed in this position!")?;
+
+        self.result = GameResult::Draw(reason);
+        info!("Game over: {}", self.result);
+
+        Ok(())
+    }
+
+    // Switches the point of view (animated)
+    pub fn flip_board(&mut self) {
+        self.set_pov(self.board.pov.opposite());
+    }
+
+    // Rotates the board to the point of view of the given side. The board takes the shorter way,
+    // so that flipping during a flip turns it back
+    pub fn set_pov(&mut self, pov: PieceColor) {
+        if self.board.pov == pov {
+            return;
+        }
+
+        self.board.pov = pov;
+
+        let from = self.board.rotation.rem_euclid(360.0);
+        let mut angle = (pov_rotation(pov) - from).rem_euclid(360.0);
+
+        if angle > 180.0 {
+            angle -= 360.0;
+        }
+
+        let duration = if self.animation_duration.is_zero() {
+            Duration::ZERO
+        } else {
+            BOARD_FLIP_DURATION
+        };
+
+        self.board_flip = Some(RotationAnimation {
+            from,
+            to: from + angle,
+            animation: Animation::new(duration),
+        });
+    }
+
+    // Both sides are played by humans at the same screen
+    pub fn is_hot_seat(&self) -> bool {
+        self.white_player.kind == PlayerKind::Human && self.black_player.kind == PlayerKind::Human
+    }
+
+    pub fn player(&self, color: PieceColor) -> &Player {
+        match color {
+            PieceColor::White => &self.white_player,
+            PieceColor::Black => &self.black_player,
+        }
+    }
+
+    // Lets the engines play a move if it is their turn. The searches run in the background, so this
+    // only starts them and polls for their results
+    pub fn update(&mut self) {
+        self.update_clock();
+        self.update_search();
+        self.update_external_engine();
+    }
+
+    // Starts the clock with the first update and ends the game when the time has run out
+    fn update_clock(&mut self) {
+        let Some(clock) = self.clock.as_mut() else {
+            return;
+        };
+
+        let now = platform::monotonic_time();
+
+        if self.result.is_over() {
+            clock.stop(now);
+            return;
+        }
+
+        if let Some(flagged) = clock.check_flag(now) {
+            self.result = timeout_result(&self.position(), flagged);
+            info!("Game over: {}", self.result);
+            return;
+        }
+
+        if !clock.is_running() {
+            clock.start(self.side_to_move, now);
+        }
+    }
+
+    // Time the engine which is to move may think about its move
+    fn engine_time_limit(&self) -> Duration {
+        let Some(clock) = &self.clock else {
+            return ENGINE_TIME_LIMIT;
+        };
+
+        let color = self.side_to_move;
+
+        time_for_move(
+            clock.remaining(color, platform::monotonic_time()),
+            clock.increment(color),
+            clock.moves_to_go(color),
+        )
+    }
+
+    fn update_search(&mut self) {
+        let result = self
+            .search_service
+            .as_mut()
+            .and_then(|search_service| search_service.poll());
+
+        if let Some(result) = result {
+            info!(
+                "Engine: depth: {} / score: {} / nodes: {}",
+                result.depth, result.score, result.nodes
+            );
+
+            if let Some(r#move) = result.best_move {
+                if let Err(e) = self.make_move(r#move) {
+                    error!("{}", e);
+                }
+            }
+        }
+
+        // The engine waits while moves which have been taken back can still be replayed
+        if self.result.is_over()
+            || self.player(self.side_to_move).kind != PlayerKind::Engine
+            || !self.future.is_empty()
+        {
+            return;
+        }
+
+        let position = self.position();
+        let evaluation = self.evaluation.clone();
+        let time_limit = self.engine_time_limit();
+        let hash_size_mb = self.hash_size_mb;
+        let search_service = self
+            .search_service
+            .get_or_insert_with(|| SearchService::new(hash_size_mb));
+
+        if !search_service.is_searching() {
+            search_service.start(
+                position,
+                self.position_hashes.clone(),
+                evaluation,
+                SearchLimits {
+                    max_depth: MAX_PLY as u32,
+                    time_limit: Some(time_limit),
+                },
+            );
+        }
+    }
+
+    fn update_external_engine(&mut self) {
+        let Some(external_engine) = self.external_engine.as_mut() else {
+            return;
+        };
+
+        let mut best_move = None;
+
+        for event in external_engine.poll() {
+            match event {
+                EngineEvent::Info(info) => {
+                    if self.analysis_enabled {
+                        debug!(
+                            "Analysis: depth: {:?} / score: {:?} / pv: {}",
+                            info.depth,
+                            info.score,
+                            info.pv.join(" ")
+                        );
+                    }
+
+                    self.analysis = Some(info);
+                }
+                EngineEvent::BestMove(text) => best_move = Some(text),
+            }
+        }
+
+        // Best moves of the analysis are not played
+        if let Some(text) = best_move {
+            if self.is_external_engine_to_move() {
+                let result = uci::parse_move(&self.position(), &text)
+                    .and_then(|r#move| self.make_move(r#move));
+
+                if let Err(e) = result {
+                    error!("{}", e);
+                }
+            }
+        }
+
+        let plies = self.history.len();
+        let is_current = self.external_engine_plies == Some(plies);
+
+        let limit = if self.is_external_engine_to_move() {
+            EngineLimit::MoveTime(self.engine_time_limit())
+        } else if self.analysis_enabled && !self.result.is_over() {
+            EngineLimit::Infinite
+        } else {
+            // Nothing to search
+            let result = self
+                .external_engine
+                .as_mut()
+                .map_or(Ok(()), |external_engine| external_engine.cancel());
+
+            if let Err(e) = result {
+                error!("{}", e);
+            }
+
+            self.external_engine_plies = None;
+            return;
+        };
+
+        let Some(external_engine) = self.external_engine.as_mut() else {
+            return;
+        };
+
+        if is_current && (external_engine.is_searching() || matches!(limit, EngineLimit::Infinite))
+        {
+            return;
+        }
+
+        self.analysis = None;
+        self.external_engine_plies = Some(plies);
+
+        if let Err(e) = external_engine.start(&self.starting_fen, &self.history, limit) {
+            error!("{}", e);
+        }
+    }
+
+    fn is_external_engine_to_move(&self) -> bool {
+        !self.result.is_over()
+            && self.player(self.side_to_move).kind == PlayerKind::ExternalEngine
+            && self.future.is_empty()
+    }
+
+    pub fn make_san_move(&mut self, text: &str) -> Result<(), Box<dyn Error>> {
+        let r#move = san::parse(&self.position(), text)?;
+
+        self.make_move(r#move)
+    }
+
+    pub fn handle_input(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Resized { width, height } => {
+                self.window_width = width;
+                self.window_height = height;
+            }
+            InputEvent::MouseButtonPressed { button, x, y } => {
+                debug!(
+                    "Mouse button pressed: {:?} / x: {} / y: {} / square: {:?}",
+                    button,
+                    x,
+                    y,
+                    self.square_at(x, y)
+                );
+                (self.cursor_x, self.cursor_y) = (x, y);
+
+                // The keyboard cursor is hidden while the mouse is used
+                self.cursor_square = None;
+
+                match button {
+                    MouseButton::Left => self.press_square(self.square_at(x, y)),
+                    // Cancel the current selection (and drag)
+                    MouseButton::Right => {
+                        self.selected_square = None;
+                        self.drag = None;
+                    }
+                    MouseButton::Middle => (),
+                }
+            }
+            InputEvent::MouseButtonReleased { button, x, y } => {
+                debug!("Mouse button released: {:?} / x: {} / y: {}", button, x, y);
+                (self.cursor_x, self.cursor_y) = (x, y);
+
+                if button == MouseButton::Left {
+                    self.release_square(self.square_at(x, y));
+                }
+            }
+            InputEvent::MouseMoved { x, y } => (self.cursor_x, self.cursor_y) = (x, y),
+            InputEvent::KeyPressed { key } => {
+                debug!("Key pressed: {:?}", key);
+
+                if self.type_move_text(key) {
+                    return;
+                }
+
+                match self.key_bindings.action(key) {
+                    Some(action) => self.perform_action(action),
+                    None => self.handle_cursor_key(key),
+                }
+            }
+            InputEvent::KeyReleased { key } => debug!("Key released: {:?}", key),
+        }
+    }
+
+    // Returns the square at a window position (in pixels)
+    pub fn square_at(&self, x: i32, y: i32) -> Option<(u8, u8)> {
+        let (world_x, world_y) = self.world_position(x, y)?;
+
+        self.board.square_at(world_x, world_y)
+    }
+
+    // Returns the world position at a window position (in pixels)
+    fn world_position(&self, x: i32, y: i32) -> Option<(f32, f32)> {
+        if self.window_width <= 0 || self.window_height <= 0 {
+            return None;
+        }
+
+        // Pixels to normalized device coordinates (the y axis is pointing upwards)
+        let ndc_x = 2.0 * (x as f32 + 0.5) / self.window_width as f32 - 1.0;
+        let ndc_y = 1.0 - 2.0 * (y as f32 + 0.5) / self.window_height as f32;
+
+        Some(inverse_orthogonal_projection(
+            &self.projection(),
+            ndc_x,
+            ndc_y,
+        ))
+    }
+
+    // Plays a move from the selected square (click-to-move) or selects (and starts dragging) a
+    // piece of the side to move
+    fn press_square(&mut self, square: Option<(u8, u8)>) {
+        self.drag = None;
+
+        let Some((board_x, board_y)) = square else {
+            self.selected_square = None;
+            return;
+        };
+
+        if let Some(from) = self.selected_square {
+            if from != (board_x, board_y) && self.try_move(from, (board_x, board_y)) {
+                return;
+            }
+        }
+
+        if !self.is_selectable((board_x, board_y)) {
+            self.selected_square = None;
+            return;
+        }
+
+        let Some((cursor_x, cursor_y)) = self.world_position(self.cursor_x, self.cursor_y) else {
+            return;
+        };
+
+        let (cursor_x, cursor_y) = self.board.unrotate(cursor_x, cursor_y);
+        let (piece_x, piece_y) = self.board.square_position(board_x, board_y);
+
+        self.drag = Some(Drag {
+            board_x,
+            board_y,
+            offset_x: cursor_x - piece_x,
+            offset_y: cursor_y - piece_y,
+            was_selected: self.selected_square == Some((board_x, board_y)),
+        });
+        self.selected_square = Some((board_x, board_y));
+    }
+
+    // Drops the dragged piece. The piece snaps back if the drop does not result in a legal move
+    fn release_square(&mut self, square: Option<(u8, u8)>) {
+        let Some(drag) = self.drag.take() else {
+            return;
+        };
+
+        let from = (drag.board_x, drag.board_y);
+
+        match square {
+            // Clicking on a selected piece again deselects it, otherwise it stays selected for
+            // click-to-move
+            Some(to) if to == from => {
+                if drag.was_selected {
+                    self.selected_square = None;
+                }
+            }
+            Some(to) => {
+                if self.try_move(from, to) {
+                    // The dragged piece does not have to slide to its destination
+                    self.move_animations
+                        .retain(|move_animation| move_animation.from != from);
+                } else {
+                    self.selected_square = None;
+                }
+            }
+            None => self.selected_square = None,
+        }
+    }
+
+    // Whether the piece on a square may be moved by the human who is to move
+    fn is_selectable(&self, (board_x, board_y): (u8, u8)) -> bool {
+        let is_own_piece = matches!(
+            self.position().piece_at(board_x, board_y),
+            Some((color, _)) if color == self.side_to_move
+        );
+
+        is_own_piece && self.is_human_to_move()
+    }
+
+    fn is_human_to_move(&self) -> bool {
+        self.player(self.side_to_move).kind == PlayerKind::Human && !self.result.is_over()
+    }
+
+    // Collects the characters of a typed move and plays it with Enter. Returns whether the key has
+    // been used for it. Only characters which can start a move begin a new move text, so that the
+    // other keys remain available for the key bindings
+    fn type_move_text(&mut self, key: Key) -> bool {
+        if self.move_text.is_empty() && !key_bindings::starts_move(key) {
+            return false;
+        }
+
+        match key {
+            Key::Character(c) if !c.is_whitespace() => self.move_text.push(c),
+            Key::Backspace => {
+                self.move_text.pop();
+            }
+            Key::Escape => self.move_text.clear(),
+            Key::Enter => self.enter_move_text(),
+            _ => return false,
+        }
+
+        true
+    }
+
+    // Plays the typed move. The text is kept if it is not a legal move, so that it can be corrected
+    fn enter_move_text(&mut self) {
+        if !self.is_human_to_move() {
+            info!("It is not your turn! ({})", self.move_text);
+            return;
+        }
+
+        let position = self.position();
+        let text = self.move_text.trim();
+
+        // Coordinate notation (e.g. e2e4 or e7e8q) or SAN (e.g. Nf3)
+        let is_coordinate_notation = text.is_ascii()
+            && text.len() >= 4
+            && fen::parse_square(&text[0..2]).is_some()
+            && fen::parse_square(&text[2..4]).is_some();
+
+        let result = if is_coordinate_notation {
+            uci::parse_move(&position, text)
+        } else {
+            san::parse(&position, text)
+        }
+        .and_then(|r#move| self.make_move(r#move));
+
+        match result {
+            Ok(()) => {
+                self.move_text.clear();
+                self.selected_square = None;
+            }
+            Err(e) => error!("{}", e),
+        }
+    }
+
+    // Moves the keyboard cursor with the arrow keys and selects or moves pieces with Enter.
+    // Escape cancels the selection
+    fn handle_cursor_key(&mut self, key: Key) {
+        // The arrow keys move the cursor on the screen, so the directions depend on the board
+        // rotation
+        let direction = match key {
+            Key::Left => (-1.0, 0.0),
+            Key::Right => (1.0, 0.0),
+            Key::Up => (0.0, -1.0),
+            Key::Down => (0.0, 1.0),
+            Key::Enter => {
+                self.press_cursor();
+                return;
+            }
+            Key::Escape => {
+                self.selected_square = None;
+                return;
+            }
+            _ => return,
+        };
+
+        // The cursor appears on the selected piece or the king of the side to move first
+        let Some((board_x, board_y)) = self.cursor_square else {
+            self.cursor_square = self.selected_square.or_else(|| {
+                self.pieces
+                    .iter()
+                    .find(|piece| piece.color == self.side_to_move && piece.kind == PieceKind::King)
+                    .map(|king| (king.board_x, king.board_y))
+            });
+            return;
+        };
+
+        let scaled_square_size = self.board.scaled_square_size();
+        let (square_x, square_y) = self.board.square_position(board_x, board_y);
+        let (center_x, center_y) = self.board.rotate(
+            square_x + scaled_square_size / 2.0,
+            square_y + scaled_square_size / 2.0,
+        );
+
+        // The cursor stays at the edge of the board
+        if let Some(square) = self.board.square_at(
+            center_x + direction.0 * scaled_square_size,
+            center_y + direction.1 * scaled_square_size,
+        ) {
+            self.cursor_square = Some(square);
+        }
+    }
+
+    // Selects the piece under the keyboard cursor or moves the selected piece to it
+    fn press_cursor(&mut self) {
+        let Some(square) = self.cursor_square else {
+            return;
+        };
+
+        if let Some(from) = self.selected_square {
+            if from == square {
+                self.selected_square = None;
+                return;
+            }
+
+            if self.try_move(from, square) {
+                return;
+            }
+        }
+
+        self.selected_square = self.is_selectable(square).then_some(square);
+    }
+
+    // Plays the legal move between two squares (if any). Promotions are always to a queen
+    fn try_move(&mut self, from: (u8, u8), to: (u8, u8)) -> bool {
+        let r#move = self.legal_moves().iter().copied().find(|r#move| {
+            (r#move.from_x, r#move.from_y) == from
+                && (r#move.to_x, r#move.to_y) == to
+                && matches!(r#move.promotion, None | Some(PieceKind::Queen))
+        });
+
+        let Some(r#move) = r#move else {
+            return false;
+        };
+
+        if let Err(e) = self.make_move(r#move) {
+            error!("{}", e);
+            return false;
+        }
+
+        self.selected_square = None;
+
+        true
+    }
+
+    fn projection(&self) -> Mat4 {
+        orthogonal_projection(0.0, self.world_width, self.world_height, 0.0, -1.0, 1.0)
+    }
+
+    // Returns the highlighted squares (in drawing order)
+    fn highlights(&self) -> Vec<Highlight> {
+        let position = self.position();
+        let mut highlights = Vec::new();
+
+        if let Some(last_move) = self.history.last() {
+            highlights.push(Highlight::new(
+                HighlightKind::LastMove,
+                last_move.from_x,
+                last_move.from_y,
+            ));
+            highlights.push(Highlight::new(
+                HighlightKind::LastMove,
+                last_move.to_x,
+                last_move.to_y,
+            ));
+        }
+
+        if position.is_in_check(self.side_to_move) {
+            let king = self
+                .pieces
+                .iter()
+                .find(|piece| piece.color == self.side_to_move && piece.kind == PieceKind::King);
+
+            if let Some(king) = king {
+                highlights.push(Highlight::new(
+                    HighlightKind::Check,
+                    king.board_x,
+                    king.board_y,
+                ));
+            }
+        }
+
+        if let Some((board_x, board_y)) = self.selected_square {
+            highlights.push(Highlight::new(HighlightKind::Selected, board_x, board_y));
+
+            // Promotions share their destination, so only one of them is highlighted
+            for r#move in self.legal_moves.iter().filter(|r#move| {
+                (r#move.from_x, r#move.from_y) == (board_x, board_y)
+                    && matches!(r#move.promotion, None | Some(PieceKind::Queen))
+            }) {
+                let kind = if position.piece_at(r#move.to_x, r#move.to_y).is_some()
+                    || position.is_en_passant(*r#move)
+                {
+                    HighlightKind::CaptureTarget
+                } else {
+                    HighlightKind::Target
+                };
+
+                highlights.push(Highlight::new(kind, r#move.to_x, r#move.to_y));
+            }
+        }
+
+        if let Some((board_x, board_y)) = self.cursor_square {
+            highlights.push(Highlight::new(HighlightKind::Cursor, board_x, board_y));
+        }
+
+        highlights
+    }
+
+    // Returns the texts around the board
+    fn texts(&self) -> Vec<Text> {
+        let board = &self.board;
+        let scaled_square_size = self.board.scaled_square_size();
+        let board_bottom = board.y + board.height;
+        let board_right = board.x + board.width;
+        let white = Vec4::new_xyzw(1.0, 1.0, 1.0, 1.0);
+        let dimmed = Vec4::new_xyzw(1.0, 1.0, 1.0, 0.6);
+        let mut texts = Vec::new();
+
+        // Returns the center of a square (with the board rotation)
+        let square_center = |board_x: u8, board_y: u8| {
+            let (square_x, square_y) = board.square_position(board_x, board_y);
+
+            board.rotate(
+                square_x + scaled_square_size / 2.0,
+                square_y + scaled_square_size / 2.0,
+            )
+        };
+
+        // Files below and ranks left of the board
+        for index in 0..8 {
+            let mut file = Text::new(
+                fen::format_square(index, 0)[..1].to_string(),
+                0.0,
+                board_bottom + TEXT_MARGIN,
+                COORDINATE_TEXT_HEIGHT,
+                dimmed,
+            );
+            file.x = square_center(index, 0).0 - file.width() / 2.0;
+            texts.push(file);
+
+            let mut rank = Text::new(
+                (index + 1).to_string(),
+                0.0,
+                square_center(0, index).1 - COORDINATE_TEXT_HEIGHT / 2.0,
+                COORDINATE_TEXT_HEIGHT,
+                dimmed,
+            );
+            rank.x = board.x - TEXT_MARGIN - rank.width();
+            texts.push(rank);
+        }
+
+        // Typed move, result or side to move above the board
+        let status = if !self.move_text.is_empty() {
+            format!("Move: {}", self.move_text)
+        } else if self.result.is_over() {
+            format!("{} {}", pgn::result_marker(&self.result), self.result)
+        } else if claimable_draw(self.halfmove_clock, &self.position_hashes).is_some() {
+            format!("{:?} to move (draw can be claimed)", self.side_to_move)
+        } else {
+            format!("{:?} to move", self.side_to_move)
+        };
+
+        texts.push(Text::new(
+            status,
+            board.x,
+            board.y - TEXT_MARGIN - STATUS_TEXT_HEIGHT,
+            STATUS_TEXT_HEIGHT,
+            white,
+        ));
+
+        // Clocks on the sides of their players (right-aligned)
+        if let Some(clock) = &self.clock {
+            let now = platform::monotonic_time();
+
+            // Rank 1 is at the bottom if White is playing upwards
+            let bottom_color = if square_center(0, 0).1 > board.y + board.height / 2.0 {
+                PieceColor::White
+            } else {
+                PieceColor::Black
+            };
+
+            for color in [bottom_color, bottom_color.opposite()] {
+                let y = if color == bottom_color {
+                    board_bottom + 2.0 * TEXT_MARGIN + COORDINATE_TEXT_HEIGHT
+                } else {
+                    board.y - TEXT_MARGIN - CLOCK_TEXT_HEIGHT
+                };
+                let text_color = if color == self.side_to_move && !self.result.is_over() {
+                    white
+                } else {
+                    dimmed
+                };
+
+                let mut text = Text::new(
+                    format_time(clock.remaining(color, now)),
+                    0.0,
+                    y,
+                    CLOCK_TEXT_HEIGHT,
+                    text_color,
+                );
+                text.x = board_right - text.width();
+                texts.push(text);
+            }
+        }
+
+        // The latest moves right of the board (if there is enough space)
+        let move_list_x = board_right + 3.0 * TEXT_MARGIN;
+        let line_height = MOVE_LIST_TEXT_HEIGHT * 1.2;
+        let max_lines = (board.height / line_height) as usize;
+        let move_list = self.move_list();
+
+        for (index, line) in move_list
+            .iter()
+            .skip(move_list.len().saturating_sub(max_lines))
+            .enumerate()
+        {
+            let text = Text::new(
+                line.clone(),
+                move_list_x,
+                board.y + index as f32 * line_height,
+                MOVE_LIST_TEXT_HEIGHT,
+                white,
+            );
+
+            if text.x + text.width() > self.world_width {
+                break;
+            }
+
+            texts.push(text);
+        }
+
+        texts
+    }
+
+    // Returns the moves in standard algebraic notation (one line per move number). The SAN is taken
+    // from the snapshots, so that the history does not have to be replayed for every frame
+    fn move_list(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        for ply in 1..=self.history.len() {
+            // The position before the move
+            let previous = &self.snapshots[ply - 1];
+            let fullmove_number = previous.fullmove_number;
+            let san = &self.snapshots[ply].san;
+
+            match previous.side_to_move {
+                PieceColor::White => lines.push(format!("{fullmove_number:>3}. {san}")),
+                PieceColor::Black => match lines.last_mut() {
+                    Some(line) => *line = format!("{line:<12}{san}"),
+                    None => lines.push(format!("{fullmove_number:>3}. ... {san}")),
+                },
+            }
         }
+
+        lines
     }
 
     pub fn initialize() {
@@ -108,6 +1357,10 @@ impl Game {
             .unwrap_or_else(|e| fatal!("{}", e));
         let atlas_shader = shader::Shader::new("shaders/atlas.vert", "shaders/atlas.frag")
             .unwrap_or_else(|e| fatal!("{}", e));
+        let overlay_shader = shader::Shader::new("shaders/vertex.vert", "shaders/overlay.frag")
+            .unwrap_or_else(|e| fatal!("{}", e));
+        let text_shader = shader::Shader::new("shaders/atlas.vert", "shaders/text.frag")
+            .unwrap_or_else(|e| fatal!("{}", e));
 
         let mut vertex_array_object: gl::types::GLuint = 0;
         let mut element_buffer_object: gl::types::GLuint = 0;
@@ -140,6 +1393,8 @@ impl Game {
         }
 
         Board::initialize(shader);
+        Highlight::initialize(overlay_shader);
+        Text::initialize(text_shader);
         Piece::initialize(atlas_shader);
 
         unsafe {
@@ -148,7 +1403,8 @@ impl Game {
         }
     }
 
-    pub fn draw(&mut self, aspect_ratio: f32) -> Result<(), Box<dyn Error>> {
+    // delta_time is the time since the last frame, which advances the animations
+    pub fn draw(&mut self, aspect_ratio: f32, delta_time: Duration) -> Result<(), Box<dyn Error>> {
         unsafe {
             // Set the clear color (#1f9b86)
             gl::ClearColor(
@@ -173,42 +1429,223 @@ impl Game {
         }
 
         // Calculate projection
-        let projection =
-            orthogonal_projection(0.0, self.world_width, self.world_height, 0.0, -1.0, 1.0);
+        let projection = self.projection();
 
         // Center board
         self.board.x = self.world_width / 2.0 - self.board.width / 2.0;
         self.board.y = self.world_height / 2.0 - self.board.height / 2.0;
 
-        if self.board.pov == PieceColor::White {
-            // Reset board rotation
-            self.board.rotation = 0.0;
-        } else {
-            // Rotate the board by 180 degrees (clock-wise)
-            self.board.rotation = 180.0;
+        // Rotate the board to the point of view (gradually while it is being flipped)
+        match self.board_flip.as_mut() {
+            Some(board_flip) => {
+                board_flip.animation.advance(delta_time);
+
+                self.board.rotation = lerp(
+                    board_flip.from,
+                    board_flip.to,
+                    board_flip.animation.progress(),
+                );
+
+                if board_flip.animation.is_finished() {
+                    self.board_flip = None;
+                }
+            }
+            None => self.board.rotation = pov_rotation(self.board.pov),
         }
 
         // Draw board
         self.board.draw(&projection)?;
 
-        // Draw pieces
-        let ratio = self.board.width / Board::TEXTURE_SIZE as f32;
-        let scaled_piece_size = Piece::TEXTURE_SIZE as f32 * ratio;
-        let scaled_border_size = Board::BORDER_TEXTURE_SIZE as f32 * ratio;
-
-        for piece in self.pieces.iter_mut() {
-            piece.x = self.board.x
-                + scaled_border_size
-                + (piece.board_x as i8 - 7).abs() as f32 * scaled_piece_size;
-            piece.y = self.board.y
-                + scaled_border_size
-                + (piece.board_y as i8 - 7).abs() as f32 * scaled_piece_size;
+        // Draw coordinates, clocks, status and move list
+        for text in self.texts() {
+            text.draw(&projection)?;
+        }
+
+        // Draw highlights
+        for highlight in self.highlights() {
+            highlight.draw(&projection, &self.board)?;
+        }
+
+        // Advance animations
+        for move_animation in self.move_animations.iter_mut() {
+            move_animation.animation.advance(delta_time);
+        }
+
+        for capture_animation in self.capture_animations.iter_mut() {
+            capture_animation.animation.advance(delta_time);
+        }
+
+        self.move_animations
+            .retain(|move_animation| !move_animation.animation.is_finished());
+        self.capture_animations
+            .retain(|capture_animation| !capture_animation.animation.is_finished());
+
+        let scaled_piece_size = self.board.scaled_square_size();
+
+        // Draw captured pieces (below the moving piece)
+        for capture_animation in self.capture_animations.iter_mut() {
+            let piece = &mut capture_animation.piece;
+
+            (piece.x, piece.y) = self.board.square_position(piece.board_x, piece.board_y);
             piece.width = scaled_piece_size;
             piece.height = scaled_piece_size;
+            piece.alpha = 1.0 - capture_animation.animation.progress();
 
             piece.draw(&projection, &self.board)?;
         }
 
+        // Draw pieces
+        let cursor = self.world_position(self.cursor_x, self.cursor_y);
+        let mut moving_pieces = Vec::new();
+        let mut dragged_piece = None;
+
+        for (index, piece) in self.pieces.iter_mut().enumerate() {
+            (piece.x, piece.y) = self.board.square_position(piece.board_x, piece.board_y);
+            piece.width = scaled_piece_size;
+            piece.height = scaled_piece_size;
+
+            // Moving pieces slide from their previous square
+            let move_animation = self
+                .move_animations
+                .iter()
+                .find(|move_animation| move_animation.to == (piece.board_x, piece.board_y));
+
+            if let Some(move_animation) = move_animation {
+                let (from_x, from_y) = self
+                    .board
+                    .square_position(move_animation.from.0, move_animation.from.1);
+                let progress = move_animation.animation.progress();
+
+                piece.x = lerp(from_x, piece.x, progress);
+                piece.y = lerp(from_y, piece.y, progress);
+            }
+
+            match (self.drag, cursor) {
+                (Some(drag), Some((cursor_x, cursor_y)))
+                    if (piece.board_x, piece.board_y) == (drag.board_x, drag.board_y) =>
+                {
+                    // The dragged piece follows the cursor
+                    let (cursor_x, cursor_y) = self.board.unrotate(cursor_x, cursor_y);
+
+                    piece.x = cursor_x - drag.offset_x;
+                    piece.y = cursor_y - drag.offset_y;
+
+                    dragged_piece = Some(index);
+                }
+                _ if move_animation.is_some() => moving_pieces.push(index),
+                _ => piece.draw(&projection, &self.board)?,
+            }
+        }
+
+        // Draw the moving pieces and the dragged piece last, so that they are drawn on top of the
+        // other pieces
+        for index in moving_pieces.into_iter().chain(dragged_piece) {
+            self.pieces[index].draw(&projection, &self.board)?;
+        }
+
         Ok(())
     }
 }
+
+// Rotation of the board (clock-wise) for a point of view
+fn pov_rotation(pov: PieceColor) -> f32 {
+    match pov {
+        // No rotation
+        PieceColor::White => 0.0,
+        // Rotated by 180 degrees
+        PieceColor::Black => 180.0,
+    }
+}
+
+// Captures and pawn moves cannot be undone
+fn is_irreversible(position: &Position, r#move: Move) -> bool {
+    position.is_en_passant(r#move)
+        || position.piece_at(r#move.to_x, r#move.to_y).is_some()
+        || matches!(
+            position.piece_at(r#move.from_x, r#move.from_y),
+            Some((_, PieceKind::Pawn))
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_result::WinReason;
+
+    fn play(game: &mut Game, moves: &[&str]) {
+        for text in moves {
+            game.make_san_move(text).unwrap();
+        }
+    }
+
+    #[test]
+    fn move_list_follows_the_history() {
+        let mut game = Game::new();
+        play(&mut game, &["e4", "e5", "Nf3", "Nc6", "Bb5"]);
+
+        assert_eq!(
+            game.move_list(),
+            ["  1. e4     e5", "  2. Nf3    Nc6", "  3. Bb5"]
+        );
+
+        game.jump_to_ply(2).unwrap();
+        assert_eq!(game.move_list(), ["  1. e4     e5"]);
+        assert_eq!(game.legal_moves().len(), 29);
+
+        game.step_forward().unwrap();
+        assert_eq!(game.move_list(), ["  1. e4     e5", "  2. Nf3"]);
+
+        // Another move replaces the moves which have been taken back
+        play(&mut game, &["d6"]);
+        assert_eq!(game.move_list(), ["  1. e4     e5", "  2. Nf3    d6"]);
+        assert_eq!(game.legal_moves(), game.position().legal_moves());
+    }
+
+    #[test]
+    fn move_list_starts_with_black() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 40").unwrap();
+        play(&mut game, &["Kd7", "e4", "Kc6"]);
+
+        assert_eq!(game.move_list(), [" 40. ... Kd7", " 41. e4     Kc6"]);
+    }
+
+    #[test]
+    fn replaying_the_line_lets_the_engine_move() {
+        let mut game = Game::new();
+        play(&mut game, &["e4", "e5", "Nf3", "Nc6"]);
+
+        // The same move against the engine replays the rest of the line
+        game.jump_to_ply(2).unwrap();
+        play(&mut game, &["Nf3"]);
+        assert_eq!(game.plies(), 4);
+        assert_eq!(game.history.len(), 4);
+
+        // Another move replaces the rest of the line
+        game.jump_to_ply(2).unwrap();
+        play(&mut game, &["Nc3"]);
+        assert_eq!(game.plies(), 3);
+        assert_eq!(game.history.len(), 3);
+
+        // Between two humans, the line is replayed move by move
+        game.black_player.kind = PlayerKind::Human;
+        play(&mut game, &["Nc6", "Nf3"]);
+        game.jump_to_ply(2).unwrap();
+        play(&mut game, &["Nc3"]);
+        assert_eq!(game.plies(), 5);
+        assert_eq!(game.history.len(), 3);
+    }
+
+    #[test]
+    fn line_includes_the_moves_which_have_been_taken_back() {
+        let mut game = Game::new();
+        play(&mut game, &["f3", "e5", "g4", "Qh4#"]);
+        game.jump_to_ply(1).unwrap();
+
+        assert_eq!(game.line().count(), 4);
+        assert_eq!(game.result, GameResult::Ongoing);
+        assert_eq!(
+            game.line_result(),
+            GameResult::Win(PieceColor::Black, WinReason::Checkmate)
+        );
+    }
+}
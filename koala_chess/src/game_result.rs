@@ -0,0 +1,320 @@
+use crate::{
+    piece::{PieceColor, PieceKind},
+    rules::Position,
+};
+use std::fmt::{self, Display, Formatter};
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum GameResult {
+    Ongoing,
+    Win(PieceColor, WinReason),
+    Draw(DrawReason),
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum WinReason {
+    Checkmate,
+    Timeout,
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum DrawReason {
+    Stalemate,
+    // Claimed by a player (see claimable_draw)
+    FiftyMoveRule,
+    ThreefoldRepetition,
+    // The game ends without a claim
+    SeventyFiveMoveRule,
+    FivefoldRepetition,
+    InsufficientMaterial,
+    // The time has run out, but the opponent could not have checkmated
+    TimeoutVsInsufficientMaterial,
+}
+
+impl GameResult {
+    pub fn is_over(&self) -> bool {
+        *self != GameResult::Ongoing
+    }
+}
+
+impl Display for GameResult {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            GameResult::Ongoing => write!(f, "Ongoing"),
+            GameResult::Win(winner, reason) => write!(f, "{winner:?} wins by {reason}"),
+            GameResult::Draw(reason) => write!(f, "Draw by {reason}"),
+        }
+    }
+}
+
+impl Display for WinReason {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            WinReason::Checkmate => write!(f, "checkmate"),
+            WinReason::Timeout => write!(f, "timeout"),
+        }
+    }
+}
+
+impl Display for DrawReason {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            DrawReason::Stalemate => write!(f, "stalemate"),
+            DrawReason::FiftyMoveRule => write!(f, "fifty-move rule"),
+            DrawReason::ThreefoldRepetition => write!(f, "threefold repetition"),
+            DrawReason::SeventyFiveMoveRule => write!(f, "seventy-five-move rule"),
+            DrawReason::FivefoldRepetition => write!(f, "fivefold repetition"),
+            DrawReason::InsufficientMaterial => write!(f, "insufficient material"),
+            DrawReason::TimeoutVsInsufficientMaterial => {
+                write!(f, "timeout vs. insufficient material")
+            }
+        }
+    }
+}
+
+// position_hashes contains the hashes of all positions since the game started (including the current one)
+pub fn determine_result(
+    position: &Position,
+    halfmove_clock: u32,
+    position_hashes: &[u64],
+) -> GameResult {
+    let side_to_move = position.side_to_move;
+
+    if position.legal_moves().is_empty() {
+        return if position.is_in_check(side_to_move) {
+            GameResult::Win(side_to_move.opposite(), WinReason::Checkmate)
+        } else {
+            GameResult::Draw(DrawReason::Stalemate)
+        };
+    }
+
+    // 75 moves of each side without a capture or pawn move
+    if halfmove_clock >= 150 {
+        return GameResult::Draw(DrawReason::SeventyFiveMoveRule);
+    }
+
+    if repetitions(position_hashes) >= 5 {
+        return GameResult::Draw(DrawReason::FivefoldRepetition);
+    }
+
+    if has_insufficient_material(position) {
+        return GameResult::Draw(DrawReason::InsufficientMaterial);
+    }
+
+    GameResult::Ongoing
+}
+
+// Draws which a player may claim, but which do not end the game by themselves
+pub fn claimable_draw(halfmove_clock: u32, position_hashes: &[u64]) -> Option<DrawReason> {
+    // 50 moves of each side without a capture or pawn move
+    if halfmove_clock >= 100 {
+        return Some(DrawReason::FiftyMoveRule);
+    }
+
+    if repetitions(position_hashes) >= 3 {
+        return Some(DrawReason::ThreefoldRepetition);
+    }
+
+    None
+}
+
+// Number of occurrences of the current position
+fn repetitions(position_hashes: &[u64]) -> usize {
+    position_hashes.last().map_or(0, |current_hash| {
+        position_hashes
+            .iter()
+            .filter(|hash| *hash == current_hash)
+            .count()
+    })
+}
+
+// The side whose time has run out loses, unless the opponent only has a king and at most one minor
+// piece left
+pub fn timeout_result(position: &Position, flagged: PieceColor) -> GameResult {
+    let winner = flagged.opposite();
+    let pieces = |kinds: &[PieceKind]| -> u32 {
+        kinds
+            .iter()
+            .map(|kind| position.pieces(winner, *kind).count_ones())
+            .sum()
+    };
+
+    let major_pieces_and_pawns = pieces(&[PieceKind::Pawn, PieceKind::Rook, PieceKind::Queen]);
+    let minor_pieces = pieces(&[PieceKind::Knight, PieceKind::Bishop]);
+
+    if major_pieces_and_pawns == 0 && minor_pieces <= 1 {
+        GameResult::Draw(DrawReason::TimeoutVsInsufficientMaterial)
+    } else {
+        GameResult::Win(winner, WinReason::Timeout)
+    }
+}
+
+fn has_insufficient_material(position: &Position) -> bool {
+    let mut minor_pieces = 0;
+    let mut knights = 0;
+    let mut bishop_square_colors = Vec::new();
+
+    for board_y in 0..8 {
+        for board_x in 0..8 {
+            match position.piece_at(board_x, board_y) {
+                Some((_, PieceKind::Pawn | PieceKind::Rook | PieceKind::Queen)) => return false,
+                Some((_, PieceKind::Knight)) => {
+                    minor_pieces += 1;
+                    knights += 1;
+                }
+                Some((_, PieceKind::Bishop)) => {
+                    minor_pieces += 1;
+                    bishop_square_colors.push((board_x + board_y) % 2);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    // King vs. king, king and minor piece vs. king or only bishops on squares of the same color
+    minor_pieces <= 1
+        || (knights == 0
+            && bishop_square_colors
+                .iter()
+                .all(|square_color| *square_color == bishop_square_colors[0]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fen, game::Game, pgn};
+
+    fn result(fen: &str, position_hashes: &[u64]) -> GameResult {
+        let fen = fen::parse(fen).unwrap();
+        let position = Position::new(&fen.pieces, fen.side_to_move, fen.en_passant_target);
+
+        determine_result(&position, fen.halfmove_clock, position_hashes)
+    }
+
+    #[test]
+    fn checkmate() {
+        assert_eq!(
+            result("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1", &[]),
+            GameResult::Win(PieceColor::White, WinReason::Checkmate)
+        );
+    }
+
+    #[test]
+    fn stalemate() {
+        assert_eq!(
+            result("7k/5Q2/8/8/8/8/8/6K1 b - - 0 1", &[]),
+            GameResult::Draw(DrawReason::Stalemate)
+        );
+    }
+
+    #[test]
+    fn fifty_move_rule_is_claimed() {
+        assert_eq!(
+            result("4k3/8/8/8/8/8/4P3/4K3 w - - 100 80", &[]),
+            GameResult::Ongoing
+        );
+        assert_eq!(claimable_draw(99, &[]), None);
+        assert_eq!(claimable_draw(100, &[]), Some(DrawReason::FiftyMoveRule));
+    }
+
+    #[test]
+    fn seventy_five_move_rule() {
+        assert_eq!(
+            result("4k3/8/8/8/8/8/4P3/4K3 w - - 150 100", &[]),
+            GameResult::Draw(DrawReason::SeventyFiveMoveRule)
+        );
+
+        // Checkmate takes precedence
+        assert_eq!(
+            result("R5k1/5ppp/8/8/8/8/8/6K1 b - - 150 100", &[]),
+            GameResult::Win(PieceColor::White, WinReason::Checkmate)
+        );
+    }
+
+    #[test]
+    fn threefold_repetition_is_claimed() {
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1";
+
+        assert_eq!(result(fen, &[1, 2, 1, 2, 1]), GameResult::Ongoing);
+        assert_eq!(claimable_draw(0, &[1, 2, 1, 2]), None);
+        assert_eq!(
+            claimable_draw(0, &[1, 2, 1, 2, 1]),
+            Some(DrawReason::ThreefoldRepetition)
+        );
+    }
+
+    #[test]
+    fn fivefold_repetition() {
+        assert_eq!(
+            result(
+                "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+                &[1, 2, 1, 2, 1, 2, 1, 2, 1]
+            ),
+            GameResult::Draw(DrawReason::FivefoldRepetition)
+        );
+    }
+
+    #[test]
+    fn insufficient_material() {
+        for fen in [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4KN2 w - - 0 1",
+            "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+        ] {
+            assert_eq!(
+                result(fen, &[]),
+                GameResult::Draw(DrawReason::InsufficientMaterial),
+                "{fen}"
+            );
+        }
+
+        // Bishops on squares of different colors and two knights can still checkmate
+        assert_eq!(
+            result("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1", &[]),
+            GameResult::Ongoing
+        );
+        assert_eq!(
+            result("4k3/8/8/8/8/8/8/1N2K1N1 w - - 0 1", &[]),
+            GameResult::Ongoing
+        );
+    }
+
+    #[test]
+    fn timeout() {
+        let fen = fen::parse("4k3/8/8/8/8/8/8/3NKR2 w - - 0 1").unwrap();
+        let position = Position::new(&fen.pieces, fen.side_to_move, fen.en_passant_target);
+
+        assert_eq!(
+            timeout_result(&position, PieceColor::Black),
+            GameResult::Win(PieceColor::White, WinReason::Timeout)
+        );
+
+        // Black only has a king left, so it could not have checkmated
+        assert_eq!(
+            timeout_result(&position, PieceColor::White),
+            GameResult::Draw(DrawReason::TimeoutVsInsufficientMaterial)
+        );
+    }
+
+    #[test]
+    fn moves_continue_after_a_claimable_draw() {
+        let pgn = "1. Nf3 Nf6 2. Ng1 Ng8 3. Nf3 Nf6 4. Ng1 Ng8 5. e4 e5 1/2-1/2";
+        let game = pgn::read(pgn).unwrap().remove(0).game;
+
+        assert_eq!(game.history.len(), 10);
+
+        let mut game = Game::new();
+
+        for r#move in ["Nf3", "Nf6", "Ng1", "Ng8", "Nf3", "Nf6", "Ng1", "Ng8"] {
+            game.make_san_move(r#move).unwrap();
+        }
+
+        assert_eq!(game.result, GameResult::Ongoing);
+
+        game.claim_draw().unwrap();
+        assert_eq!(
+            game.result,
+            GameResult::Draw(DrawReason::ThreefoldRepetition)
+        );
+    }
+}
@@ -0,0 +1,39 @@
+// Platform-neutral input events. Positions are in window pixels (origin: top left)
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum InputEvent {
+    Resized { width: i32, height: i32 },
+    MouseButtonPressed { button: MouseButton, x: i32, y: i32 },
+    MouseButtonReleased { button: MouseButton, x: i32, y: i32 },
+    MouseMoved { x: i32, y: i32 },
+    // Repeated while the key is held down
+    KeyPressed { key: Key },
+    KeyReleased { key: Key },
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Key {
+    // Printable character as typed (e.g. 'f' or 'F')
+    Character(char),
+    Enter,
+    Escape,
+    Backspace,
+    Tab,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    // F1 - F12
+    Function(u8),
+}
@@ -0,0 +1,232 @@
+use crate::{config::Config, input::Key};
+use std::error::Error;
+
+// Actions which can be bound to keys
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Action {
+    FlipBoard,
+    Undo,
+    Redo,
+    ClaimDraw,
+    NewGame,
+    StepBack,
+    StepForward,
+    GoToStart,
+    GoToEnd,
+    ToggleFullscreen,
+    Quit,
+}
+
+impl Action {
+    pub const ALL: [Action; 11] = [
+        Action::FlipBoard,
+        Action::Undo,
+        Action::Redo,
+        Action::ClaimDraw,
+        Action::NewGame,
+        Action::StepBack,
+        Action::StepForward,
+        Action::GoToStart,
+        Action::GoToEnd,
+        Action::ToggleFullscreen,
+        Action::Quit,
+    ];
+
+    // Config key of the binding (e.g. key_flip_board = tab)
+    pub fn config_key(&self) -> &'static str {
+        match self {
+            Action::FlipBoard => "key_flip_board",
+            Action::Undo => "key_undo",
+            Action::Redo => "key_redo",
+            Action::ClaimDraw => "key_claim_draw",
+            Action::NewGame => "key_new_game",
+            Action::StepBack => "key_step_back",
+            Action::StepForward => "key_step_forward",
+            Action::GoToStart => "key_go_to_start",
+            Action::GoToEnd => "key_go_to_end",
+            Action::ToggleFullscreen => "key_toggle_fullscreen",
+            Action::Quit => "key_quit",
+        }
+    }
+
+    // The arrow keys, Enter and the characters of moves (e.g. "Nf3" or "e2e4") are not used, so
+    // that they remain free for entering moves
+    fn default_key(&self) -> Key {
+        match self {
+            Action::FlipBoard => Key::Tab,
+            Action::Undo => Key::Character('u'),
+            Action::Redo => Key::Character('r'),
+            Action::ClaimDraw => Key::Character('='),
+            Action::NewGame => Key::Character('n'),
+            Action::StepBack => Key::PageUp,
+            Action::StepForward => Key::PageDown,
+            Action::GoToStart => Key::Home,
+            Action::GoToEnd => Key::End,
+            Action::ToggleFullscreen => Key::Function(11),
+            Action::Quit => Key::Character('q'),
+        }
+    }
+}
+
+pub struct KeyBindings {
+    bindings: Vec<(Key, Action)>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> KeyBindings {
+        KeyBindings {
+            bindings: Action::ALL
+                .iter()
+                .map(|action| (action.default_key(), *action))
+                .collect(),
+        }
+    }
+}
+
+impl KeyBindings {
+    // Bindings which are missing in the config keep their default key. "none" unbinds an action.
+    // Keys which start a typed move or which are bound to another action are rejected, because
+    // the action would never be performed
+    pub fn from_config(config: &Config) -> Result<KeyBindings, Box<dyn Error>> {
+        let mut bindings: Vec<(Key, Action)> = Vec::new();
+
+        for action in Action::ALL {
+            let key = match config.get(action.config_key()) {
+                Some("none") => continue,
+                Some(name) => parse_key(name).ok_or_else(|| {
+                    format!(
+                        "Invalid config: unknown key for '{}'! ({name})",
+                        action.config_key()
+                    )
+                })?,
+                None => action.default_key(),
+            };
+
+            if starts_move(key) {
+                return Err(format!(
+                    "Invalid config: '{}' is bound to a key which starts a typed move! ({:?})",
+                    action.config_key(),
+                    key
+                )
+                .into());
+            }
+
+            if let Some((_, other_action)) =
+                bindings.iter().find(|(bound_key, _)| *bound_key == key)
+            {
+                return Err(format!(
+                    "Invalid config: '{}' and '{}' are bound to the same key! ({:?})",
+                    other_action.config_key(),
+                    action.config_key(),
+                    key
+                )
+                .into());
+            }
+
+            bindings.push((key, action));
+        }
+
+        Ok(KeyBindings { bindings })
+    }
+
+    pub fn action(&self, key: Key) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(bound_key, _)| *bound_key == key)
+            .map(|(_, action)| *action)
+    }
+}
+
+// Characters which start a typed move (e.g. "Nf3", "e2e4" or "O-O", see Game::type_move_text)
+pub fn starts_move(key: Key) -> bool {
+    matches!(
+        key,
+        Key::Character('a'..='h' | 'N' | 'B' | 'R' | 'Q' | 'K' | 'O' | '0')
+    )
+}
+
+// A single character (case-sensitive, e.g. "u" or "U") or the name of a special key (e.g.
+// "page_up" or "f11")
+pub fn parse_key(name: &str) -> Option<Key> {
+    let mut chars = name.chars();
+
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(Key::Character(c));
+    }
+
+    let key = match name.to_ascii_lowercase().as_str() {
+        "space" => Key::Character(' '),
+        "enter" => Key::Enter,
+        "escape" => Key::Escape,
+        "backspace" => Key::Backspace,
+        "tab" => Key::Tab,
+        "delete" => Key::Delete,
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "page_up" => Key::PageUp,
+        "page_down" => Key::PageDown,
+        name => match name
+            .strip_prefix('f')
+            .and_then(|number| number.parse::<u8>().ok())
+        {
+            Some(number @ 1..=12) => Key::Function(number),
+            _ => return None,
+        },
+    };
+
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_config(text: &str) -> Result<KeyBindings, Box<dyn Error>> {
+        KeyBindings::from_config(&Config::parse(text).unwrap())
+    }
+
+    #[test]
+    fn default_keys() {
+        let key_bindings = from_config("").unwrap();
+
+        for action in Action::ALL {
+            assert!(!starts_move(action.default_key()), "{action:?}");
+            assert_eq!(key_bindings.action(action.default_key()), Some(action));
+        }
+    }
+
+    #[test]
+    fn configured_keys() {
+        let key_bindings =
+            from_config("key_undo = backspace\nkey_redo = none\nkey_quit = F12").unwrap();
+
+        assert_eq!(key_bindings.action(Key::Backspace), Some(Action::Undo));
+        assert_eq!(key_bindings.action(Key::Character('u')), None);
+        assert_eq!(key_bindings.action(Key::Character('r')), None);
+        assert_eq!(key_bindings.action(Key::Function(12)), Some(Action::Quit));
+    }
+
+    #[test]
+    fn invalid_keys() {
+        // Unknown key names
+        assert!(from_config("key_undo = ctrl+z").is_err());
+        assert!(from_config("key_undo = f13").is_err());
+
+        // Keys which start a typed move
+        for key in ["b", "N", "O", "0"] {
+            assert!(
+                from_config(&format!("key_flip_board = {key}")).is_err(),
+                "{key}"
+            );
+        }
+
+        // Keys which are bound twice (also with a default key)
+        assert!(from_config("key_undo = z\nkey_redo = z").is_err());
+        assert!(from_config("key_flip_board = u").is_err());
+        assert!(from_config("key_flip_board = u\nkey_undo = none").is_ok());
+    }
+}
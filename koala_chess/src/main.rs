@@ -1,41 +1,321 @@
+mod animation;
+mod bitboard;
 mod bitmap;
 mod board;
+mod clock;
+mod config;
+mod evaluation;
+mod external_engine;
+mod fen;
 mod game;
+mod game_result;
+mod input;
+mod key_bindings;
 mod mat4;
+mod overlay;
+mod perft;
+mod pgn;
 mod piece;
 mod platform;
 mod player;
 mod projections;
 mod renderer;
+mod rules;
+mod san;
+mod search;
+mod search_service;
 mod shader;
+mod text;
 mod transformations;
+mod transposition_table;
+mod uci;
 mod vec3;
 mod vec4;
+mod xboard;
+mod zobrist;
 
+use clock::{Clock, TimeControl};
+use config::{Config, DEFAULT_CONFIG_PATH};
+use external_engine::ExternalEngine;
 use game::Game;
+use game_result::{DrawReason, GameResult, WinReason};
+use key_bindings::KeyBindings;
+use logger::*;
+use piece::PieceColor;
+use player::{Player, PlayerKind};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    time::Duration,
+};
 
 #[cfg(target_family = "windows")]
 fn main() {
+    // Run headless as a UCI engine (e.g. for cutechess)
+    if std::env::args().any(|arg| arg == "--uci") {
+        uci::run();
+        return;
+    }
+
+    // Run headless as a CECP engine (e.g. for XBoard)
+    if std::env::args().any(|arg| arg == "--xboard") {
+        xboard::run();
+        return;
+    }
+
+    // Count the nodes of the move tree (e.g. --perft 5 or --divide 5, optionally with --fen)
+    if let Some((depth, divide)) = perft_depth() {
+        perft::run(&create_game().position(), depth, divide);
+        return;
+    }
+
     // Create the window
     let window = platform::windows::create_window();
 
     // Initialize the game
     Game::initialize();
-    let mut game = Game::new();
+    let config = load_config();
+    let mut game = create_game();
+    configure_players(&mut game, &config);
+    configure_clock(&mut game, &config);
+    configure_display(&mut game, &config);
 
     // Enter the game loop
     platform::windows::r#loop(window, &mut game);
+
+    save_game(&game);
 }
 
 #[cfg(target_family = "unix")]
 fn main() {
+    // Run headless as a UCI engine (e.g. for cutechess)
+    if std::env::args().any(|arg| arg == "--uci") {
+        uci::run();
+        return;
+    }
+
+    // Run headless as a CECP engine (e.g. for XBoard)
+    if std::env::args().any(|arg| arg == "--xboard") {
+        xboard::run();
+        return;
+    }
+
+    // Count the nodes of the move tree (e.g. --perft 5 or --divide 5, optionally with --fen)
+    if let Some((depth, divide)) = perft_depth() {
+        perft::run(&create_game().position(), depth, divide);
+        return;
+    }
+
     // Create the window
     let (display, window) = platform::unix::create_window();
 
     // Initialize the game
     Game::initialize();
-    let mut game = Game::new();
+    let config = load_config();
+    let mut game = create_game();
+    configure_players(&mut game, &config);
+    configure_clock(&mut game, &config);
+    configure_display(&mut game, &config);
 
     // Enter the game loop
-    platform::unix::r#loop(display, window, &mut game)
+    platform::unix::r#loop(display, window, &mut game);
+
+    save_game(&game);
+}
+
+fn create_game() -> Game {
+    let args: Vec<String> = std::env::args().collect();
+
+    // Start from a specific position (e.g. --fen "8/8/8/4k3/8/8/4P3/4K3 w - - 0 1")
+    if let Some(fen) = argument_value(&args, "--fen") {
+        return Game::from_fen(fen).unwrap_or_else(|e| fatal!("{}", e));
+    }
+
+    // Continue the first game of a PGN file (e.g. --pgn game.pgn)
+    if let Some(path) = argument_value(&args, "--pgn") {
+        let pgn = fs::read_to_string(path)
+            .unwrap_or_else(|e| fatal!("Could not read PGN file! ({}) ({})", path, e));
+        let pgn_game = pgn::read(&pgn)
+            .unwrap_or_else(|e| fatal!("{}", e))
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| fatal!("The PGN file does not contain a game! ({})", path));
+
+        info!(
+            "Loaded PGN game: {} vs. {}",
+            pgn_game.tag("White").unwrap_or("?"),
+            pgn_game.tag("Black").unwrap_or("?")
+        );
+
+        return pgn_game.game;
+    }
+
+    Game::new()
+}
+
+// Returns the depth and whether to divide (if perft has been requested)
+fn perft_depth() -> Option<(u32, bool)> {
+    let args: Vec<String> = std::env::args().collect();
+
+    let (depth, divide) = match argument_value(&args, "--perft") {
+        Some(depth) => (depth, false),
+        None => (argument_value(&args, "--divide")?, true),
+    };
+
+    let depth = depth
+        .parse()
+        .unwrap_or_else(|_| fatal!("Invalid perft depth! ({})", depth));
+
+    Some((depth, divide))
+}
+
+fn load_config() -> Config {
+    let args: Vec<String> = std::env::args().collect();
+
+    // Use a specific config file (e.g. --config koala_chess.cfg)
+    let path = argument_value(&args, "--config").map_or(DEFAULT_CONFIG_PATH, String::as_str);
+
+    Config::load(path).unwrap_or_else(|e| fatal!("{}", e))
+}
+
+fn configure_players(game: &mut Game, config: &Config) {
+    let args: Vec<String> = std::env::args().collect();
+
+    // Play against a human or the external engine instead of the built-in engine
+    // (e.g. --opponent human)
+    let opponent_kind = match argument_value(&args, "--opponent").map(String::as_str) {
+        None | Some("engine") => PlayerKind::Engine,
+        Some("human") => PlayerKind::Human,
+        Some("external") => PlayerKind::ExternalEngine,
+        Some(opponent) => fatal!("Unknown opponent! ({})", opponent),
+    };
+
+    // Let the external engine analyze the positions (e.g. engine_analysis = true)
+    game.analysis_enabled = config
+        .get_parsed("engine_analysis")
+        .unwrap_or_else(|e| fatal!("{}", e))
+        .unwrap_or(false);
+
+    // Size of the transposition table of the built-in engine in MB (e.g. hash_size = 64)
+    if let Some(hash_size_mb) = config
+        .get_parsed("hash_size")
+        .unwrap_or_else(|e| fatal!("{}", e))
+    {
+        game.hash_size_mb = hash_size_mb;
+    }
+
+    // The external engine is used as the opponent or for the analysis
+    // (e.g. engine_path = /usr/games/stockfish)
+    if opponent_kind == PlayerKind::ExternalEngine || game.analysis_enabled {
+        let path = config
+            .get("engine_path")
+            .unwrap_or_else(|| fatal!("The external engine requires engine_path in the config!"));
+
+        game.external_engine =
+            Some(ExternalEngine::spawn(path).unwrap_or_else(|e| fatal!("{}", e)));
+    }
+
+    // Play with the black pieces (e.g. --color black)
+    let human_color = match argument_value(&args, "--color").map(String::as_str) {
+        None | Some("white") => PieceColor::White,
+        Some("black") => PieceColor::Black,
+        Some(color) => fatal!("Unknown color! ({})", color),
+    };
+
+    for player in [&mut game.white_player, &mut game.black_player] {
+        player.kind = if player.piece_color == human_color {
+            PlayerKind::Human
+        } else {
+            opponent_kind
+        };
+    }
+}
+
+fn configure_clock(game: &mut Game, config: &Config) {
+    // Play with a clock (e.g. time_control = 300+2, see TimeControl::parse)
+    if let Some(time_control) = config.get("time_control") {
+        let time_control = TimeControl::parse(time_control).unwrap_or_else(|e| fatal!("{}", e));
+        game.clock = Some(Clock::new(time_control));
+    }
+}
+
+fn configure_display(game: &mut Game, config: &Config) {
+    // Duration of the piece movements in milliseconds (e.g. animation_duration = 300, 0 disables
+    // the animations)
+    if let Some(animation_duration) = config
+        .get_parsed("animation_duration")
+        .unwrap_or_else(|e| fatal!("{}", e))
+    {
+        game.animation_duration = Duration::from_millis(animation_duration);
+    }
+
+    // Flip the board to the side to move in hot-seat games (e.g. auto_flip = true)
+    game.auto_flip = config
+        .get_parsed("auto_flip")
+        .unwrap_or_else(|e| fatal!("{}", e))
+        .unwrap_or(false);
+
+    if game.auto_flip && game.is_hot_seat() {
+        game.board.pov = game.side_to_move;
+    }
+
+    // Keys of the actions (e.g. key_undo = backspace, see key_bindings::parse_key)
+    game.key_bindings = KeyBindings::from_config(config).unwrap_or_else(|e| fatal!("{}", e));
+}
+
+fn save_game(game: &Game) {
+    let args: Vec<String> = std::env::args().collect();
+
+    // Append the game to a PGN file (e.g. --save-pgn games.pgn)
+    if let Some(path) = argument_value(&args, "--save-pgn") {
+        let player_name = |player: &Player| match player.kind {
+            PlayerKind::Human => "Human".to_string(),
+            PlayerKind::Engine => "Koala Chess".to_string(),
+            PlayerKind::ExternalEngine => game
+                .external_engine
+                .as_ref()
+                .map_or("?".to_string(), |external_engine| {
+                    external_engine.name.clone()
+                }),
+        };
+
+        let mut tags = vec![
+            ("Event".to_string(), "Koala Chess".to_string()),
+            ("White".to_string(), player_name(&game.white_player)),
+            ("Black".to_string(), player_name(&game.black_player)),
+        ];
+
+        if let Some(clock) = &game.clock {
+            tags.push(("TimeControl".to_string(), clock.time_control.to_string()));
+        }
+
+        if matches!(
+            game.result,
+            GameResult::Win(_, WinReason::Timeout)
+                | GameResult::Draw(DrawReason::TimeoutVsInsufficientMaterial)
+        ) {
+            tags.push(("Termination".to_string(), "time forfeit".to_string()));
+        }
+
+        let result = pgn::write(game, &tags).and_then(|pgn| {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{pgn}")?;
+
+            Ok(())
+        });
+
+        match result {
+            Ok(()) => info!("Saved PGN game: {}", path),
+            Err(e) => error!("Could not save PGN game! ({}) ({})", path, e),
+        }
+    }
+}
+
+fn argument_value<'a>(args: &'a [String], name: &str) -> Option<&'a String> {
+    let index = args.iter().position(|arg| arg == name)?;
+
+    Some(
+        args.get(index + 1)
+            .unwrap_or_else(|| fatal!("{} requires a value!", name)),
+    )
 }
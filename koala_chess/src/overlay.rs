@@ -0,0 +1,171 @@
+use crate::{
+    board::Board,
+    mat4::Mat4,
+    shader::Shader,
+    transformations::{rotate_z, scale, translate},
+    vec3::Vec3,
+    vec4::Vec4,
+};
+use logger::*;
+use std::{
+    error::Error,
+    sync::{LazyLock, Mutex},
+};
+
+static OVERLAY_SHADER: LazyLock<Mutex<Option<Shader>>> = LazyLock::new(|| Mutex::new(None));
+static mut VERTEX_BUFFER_OBJECT: gl::types::GLuint = 0;
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum HighlightKind {
+    Selected,
+    LastMove,
+    // Legal destination of the selected piece (empty square)
+    Target,
+    // Legal destination of the selected piece (occupied square)
+    CaptureTarget,
+    Check,
+    // Square of the keyboard cursor
+    Cursor,
+}
+
+// A tinted square which is drawn between the board and the pieces
+pub struct Highlight {
+    pub kind: HighlightKind,
+    pub board_x: u8,
+    pub board_y: u8,
+}
+
+impl Highlight {
+    pub fn initialize(overlay_shader: Shader) {
+        *OVERLAY_SHADER
+            .lock()
+            .unwrap_or_else(|e| fatal!("Could not lock overlay shader mutex! ({})", e)) =
+            Some(overlay_shader);
+
+        #[rustfmt::skip]
+        let vertices: [f32; 16] = [
+            // positions, texture coordinates
+            0.0, 0.0,     0.0, 0.0, // top left
+            1.0, 0.0,     1.0, 0.0, // top right
+            1.0, 1.0,     1.0, 1.0, // bottom right
+            0.0, 1.0,     0.0, 1.0, // bottom left
+        ];
+
+        unsafe {
+            // Generate vertex buffer object
+            gl::GenBuffers(1, std::ptr::addr_of_mut!(VERTEX_BUFFER_OBJECT));
+
+            // Bind vertex buffer object
+            gl::BindBuffer(gl::ARRAY_BUFFER, VERTEX_BUFFER_OBJECT);
+
+            // Set vertex buffer object data
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                std::mem::size_of_val(&vertices) as gl::types::GLsizeiptr,
+                vertices.as_ptr() as *const std::ffi::c_void,
+                gl::STATIC_DRAW,
+            );
+        }
+    }
+
+    pub fn new(kind: HighlightKind, board_x: u8, board_y: u8) -> Highlight {
+        Highlight {
+            kind,
+            board_x,
+            board_y,
+        }
+    }
+
+    pub fn draw(&self, projection: &Mat4, board: &Board) -> Result<(), Box<dyn Error>> {
+        unsafe {
+            // Bind vertex buffer object
+            gl::BindBuffer(gl::ARRAY_BUFFER, VERTEX_BUFFER_OBJECT);
+
+            // Position attribute
+            gl::VertexAttribPointer(
+                0,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                16,
+                std::ptr::null::<std::ffi::c_void>(),
+            );
+            gl::EnableVertexAttribArray(0);
+
+            // Texture coordinates attribute
+            gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, 16, 8 as *const std::ffi::c_void);
+            gl::EnableVertexAttribArray(1);
+        }
+
+        // Use specific shader
+        let overlay_shader_mutex = OVERLAY_SHADER
+            .lock()
+            .unwrap_or_else(|e| fatal!("Could not lock overlay shader mutex! {}", e));
+        let overlay_shader = overlay_shader_mutex
+            .unwrap_or_else(|| fatal!("Overlay shader has not been initialized yet!"));
+        overlay_shader.r#use();
+
+        // Calculate model. The square center is rotated around the board center and the square
+        // around its center (which only matters while the board is being flipped)
+        let scaled_square_size = board.scaled_square_size();
+        let (square_x, square_y) = board.square_position(self.board_x, self.board_y);
+        let (square_center_x, square_center_y) = board.rotate(
+            square_x + scaled_square_size / 2.0,
+            square_y + scaled_square_size / 2.0,
+        );
+
+        let mut model = Mat4::identity();
+        model = translate(
+            model,
+            Vec3::new_xyz(
+                square_center_x - scaled_square_size / 2.0,
+                square_center_y - scaled_square_size / 2.0,
+                0.0,
+            ),
+        );
+        model = translate(
+            model,
+            Vec3::new_xyz(scaled_square_size / 2.0, scaled_square_size / 2.0, 0.0),
+        );
+        model = rotate_z(model, board.rotation);
+        model = translate(
+            model,
+            Vec3::new_xyz(-scaled_square_size / 2.0, -scaled_square_size / 2.0, 0.0),
+        );
+        model = scale(
+            model,
+            Vec3::new_xyz(scaled_square_size, scaled_square_size, 1.0),
+        );
+
+        overlay_shader.set_mat4("model\0", model.data.as_ptr() as *const gl::types::GLfloat)?;
+        overlay_shader.set_mat4(
+            "projection\0",
+            projection.data.as_ptr() as *const gl::types::GLfloat,
+        )?;
+
+        let (color, shape) = match self.kind {
+            // Yellow square
+            HighlightKind::Selected => (Vec4::new_xyzw(1.0, 1.0, 0.2, 0.45), 0.0),
+            // Light yellow square
+            HighlightKind::LastMove => (Vec4::new_xyzw(1.0, 0.9, 0.3, 0.3), 0.0),
+            // Dark dot
+            HighlightKind::Target => (Vec4::new_xyzw(0.0, 0.0, 0.0, 0.25), 1.0),
+            // Dark ring
+            HighlightKind::CaptureTarget => (Vec4::new_xyzw(0.0, 0.0, 0.0, 0.25), 2.0),
+            // Red glow
+            HighlightKind::Check => (Vec4::new_xyzw(1.0, 0.0, 0.0, 0.9), 3.0),
+            // Blue frame
+            HighlightKind::Cursor => (Vec4::new_xyzw(0.2, 0.5, 1.0, 0.9), 4.0),
+        };
+
+        overlay_shader.set_vec4("color\0", color)?;
+        overlay_shader.set_float("shape\0", shape)?;
+
+        // Draw elements
+        unsafe {
+            gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, std::ptr::null());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{
+    rules::{Move, Position},
+    uci,
+};
+use std::time::Instant;
+
+// Counts the leaf nodes of the move tree up to the given depth. The counts of well-known positions
+// are used to verify the move generation
+// Reference: https://www.chessprogramming.org/Perft
+pub fn perft(position: &mut Position, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = position.legal_moves();
+
+    // The leaves do not have to be made (bulk counting)
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    moves
+        .into_iter()
+        .map(|r#move| count_after_move(position, r#move, depth))
+        .sum()
+}
+
+// Returns the node count of every move, which helps to find the move that differs from the
+// counts of another move generator
+pub fn divide(position: &mut Position, depth: u32) -> Vec<(Move, u64)> {
+    position
+        .legal_moves()
+        .into_iter()
+        .map(|r#move| (r#move, count_after_move(position, r#move, depth.max(1))))
+        .collect()
+}
+
+// Prints the node count (and the node count of every move, if divide is set) to stdout
+pub fn run(position: &Position, depth: u32, divide: bool) {
+    let mut position = position.clone();
+    let start = Instant::now();
+
+    let nodes = if divide {
+        let mut nodes = 0;
+
+        for (r#move, move_nodes) in self::divide(&mut position, depth) {
+            println!("{}: {move_nodes}", uci::format_move(r#move));
+            nodes += move_nodes;
+        }
+
+        println!();
+
+        nodes
+    } else {
+        perft(&mut position, depth)
+    };
+
+    let elapsed = start.elapsed();
+
+    println!("Nodes: {nodes}");
+    println!(
+        "Time: {}ms / nodes per second: {}",
+        elapsed.as_millis(),
+        (nodes as f64 / elapsed.as_secs_f64().max(f64::EPSILON)) as u64
+    );
+}
+
+fn count_after_move(position: &mut Position, r#move: Move, depth: u32) -> u64 {
+    let undo = position.make_move(r#move);
+    let nodes = perft(position, depth - 1);
+    position.unmake_move(r#move, undo);
+
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen;
+
+    // Reference: https://www.chessprogramming.org/Perft_Results
+    const START_POSITION: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+    const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+    const POSITION_4_MIRRORED: &str =
+        "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1";
+    const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+    const POSITION_6: &str =
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+
+    fn assert_perft(fen: &str, expected_nodes: &[u64]) {
+        let fen = fen::parse(fen).unwrap();
+        let mut position = Position::new(&fen.pieces, fen.side_to_move, fen.en_passant_target);
+
+        for (depth, expected_nodes) in (1..).zip(expected_nodes) {
+            assert_eq!(
+                perft(&mut position, depth),
+                *expected_nodes,
+                "depth {depth}"
+            );
+        }
+    }
+
+    #[test]
+    fn start_position() {
+        assert_perft(START_POSITION, &[20, 400, 8_902, 197_281]);
+    }
+
+    #[test]
+    fn kiwipete() {
+        assert_perft(KIWIPETE, &[48, 2_039, 97_862]);
+    }
+
+    #[test]
+    fn position_3() {
+        assert_perft(POSITION_3, &[14, 191, 2_812, 43_238]);
+    }
+
+    #[test]
+    fn position_4() {
+        assert_perft(POSITION_4, &[6, 264, 9_467]);
+        assert_perft(POSITION_4_MIRRORED, &[6, 264, 9_467]);
+    }
+
+    #[test]
+    fn position_5() {
+        assert_perft(POSITION_5, &[44, 1_486, 62_379]);
+    }
+
+    #[test]
+    fn position_6() {
+        assert_perft(POSITION_6, &[46, 2_079, 89_890]);
+    }
+
+    // The deeper searches take a while (run with cargo test --release -- --ignored)
+    #[test]
+    #[ignore]
+    fn deep() {
+        assert_perft(START_POSITION, &[20, 400, 8_902, 197_281, 4_865_609]);
+        assert_perft(KIWIPETE, &[48, 2_039, 97_862, 4_085_603]);
+        assert_perft(POSITION_3, &[14, 191, 2_812, 43_238, 674_624]);
+        assert_perft(POSITION_4, &[6, 264, 9_467, 422_333]);
+        assert_perft(POSITION_5, &[44, 1_486, 62_379, 2_103_487]);
+        assert_perft(POSITION_6, &[46, 2_079, 89_890, 3_894_594]);
+    }
+
+    #[test]
+    fn divide_sums_up_to_perft() {
+        let fen = fen::parse(KIWIPETE).unwrap();
+        let mut position = Position::new(&fen.pieces, fen.side_to_move, fen.en_passant_target);
+
+        let moves = divide(&mut position, 2);
+
+        assert_eq!(moves.len(), 48);
+        assert_eq!(moves.iter().map(|(_, nodes)| nodes).sum::<u64>(), 2_039);
+    }
+}
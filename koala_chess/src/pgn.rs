@@ -0,0 +1,513 @@
+use crate::{
+    fen::{self, STARTING_FEN},
+    game::Game,
+    game_result::{claimable_draw, GameResult},
+    piece::PieceColor,
+    rules::Position,
+    san,
+};
+use std::error::Error;
+
+// Tags which are always written (in this order), see the "Seven Tag Roster"
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+const MAX_LINE_LENGTH: usize = 80;
+
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub game: Game,
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag_name, _)| tag_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+enum Token {
+    Tag(String, String),
+    Symbol(String),
+    VariationStart,
+    VariationEnd,
+}
+
+// Reads all games of a PGN file. Comments, NAGs and variations are skipped and only the main line
+// is replayed
+// Reference: https://www.saremba.de/chessgml/standards/pgn/pgn-complete.htm
+pub fn read(pgn: &str) -> Result<Vec<PgnGame>, Box<dyn Error>> {
+    let mut games = Vec::new();
+    let mut tags = Vec::new();
+    let mut symbols = Vec::new();
+    let mut variation_depth = 0;
+
+    for token in tokenize(pgn)? {
+        match token {
+            Token::Tag(name, value) => {
+                // A tag after some movetext starts a new game (even if the previous game did not
+                // have a game termination marker)
+                if !symbols.is_empty() {
+                    games.push(replay(std::mem::take(&mut tags), &symbols, None)?);
+                    symbols.clear();
+                }
+
+                tags.push((name, value));
+            }
+            Token::VariationStart => variation_depth += 1,
+            Token::VariationEnd => {
+                if variation_depth == 0 {
+                    return Err("Invalid PGN: unexpected ')'!".into());
+                }
+
+                variation_depth -= 1;
+            }
+            Token::Symbol(symbol) if variation_depth == 0 => {
+                if is_game_termination_marker(&symbol) {
+                    games.push(replay(std::mem::take(&mut tags), &symbols, Some(&symbol))?);
+                    symbols.clear();
+                } else if !is_move_number(&symbol) {
+                    symbols.push(symbol);
+                }
+            }
+            Token::Symbol(_) => (),
+        }
+    }
+
+    if !tags.is_empty() || !symbols.is_empty() {
+        games.push(replay(tags, &symbols, None)?);
+    }
+
+    Ok(games)
+}
+
+pub fn write(game: &Game, tags: &[(String, String)]) -> Result<String, Box<dyn Error>> {
+    // Results which are not determined by the rules (e.g. resignation) can be passed as a tag
+    // The whole line is written, even if some of its moves have been taken back
+    let result = match game.line_result() {
+        GameResult::Ongoing => tags
+            .iter()
+            .find(|(name, value)| name == "Result" && is_game_termination_marker(value))
+            .map_or("*", |(_, value)| value.as_str()),
+        result => result_marker(&result),
+    };
+    let mut pgn = String::new();
+
+    for name in SEVEN_TAG_ROSTER {
+        let value = if name == "Result" {
+            result
+        } else {
+            tags.iter()
+                .find(|(tag_name, _)| tag_name == name)
+                .map_or(unknown_tag_value(name), |(_, value)| value.as_str())
+        };
+
+        pgn.push_str(&format_tag(name, value));
+    }
+
+    if game.starting_fen != STARTING_FEN {
+        pgn.push_str(&format_tag("SetUp", "1"));
+        pgn.push_str(&format_tag("FEN", &game.starting_fen));
+    }
+
+    for (name, value) in tags {
+        if !SEVEN_TAG_ROSTER.contains(&name.as_str()) && name != "SetUp" && name != "FEN" {
+            pgn.push_str(&format_tag(name, value));
+        }
+    }
+
+    pgn.push('\n');
+
+    // Replay the moves on a position to be able to format every move
+    let fen = fen::parse(&game.starting_fen)?;
+    let mut position = Position::new(&fen.pieces, fen.side_to_move, fen.en_passant_target);
+    let mut fullmove_number = fen.fullmove_number;
+    let mut movetext = Vec::new();
+
+    for (index, r#move) in game.line().enumerate() {
+        if position.side_to_move == PieceColor::White {
+            movetext.push(format!("{fullmove_number}."));
+        } else if index == 0 {
+            movetext.push(format!("{fullmove_number}..."));
+        }
+
+        movetext.push(san::format(&position, *r#move));
+
+        if position.side_to_move == PieceColor::Black {
+            fullmove_number += 1;
+        }
+
+        position.make_move(*r#move);
+    }
+
+    movetext.push(result.to_string());
+
+    // Wrap the movetext
+    let mut line_length = 0;
+
+    for symbol in movetext {
+        if line_length > 0 && line_length + 1 + symbol.len() > MAX_LINE_LENGTH {
+            pgn.push('\n');
+            line_length = 0;
+        } else if line_length > 0 {
+            pgn.push(' ');
+            line_length += 1;
+        }
+
+        line_length += symbol.len();
+        pgn.push_str(&symbol);
+    }
+
+    pgn.push('\n');
+
+    Ok(pgn)
+}
+
+// The game termination marker (or else the Result tag) tells whether a draw has been claimed
+fn replay(
+    tags: Vec<(String, String)>,
+    symbols: &[String],
+    marker: Option<&str>,
+) -> Result<PgnGame, Box<dyn Error>> {
+    let tag = |tag_name: &str| {
+        tags.iter()
+            .find(|(name, _)| name == tag_name)
+            .map(|(_, value)| value.as_str())
+    };
+
+    let mut game = Game::from_fen(tag("FEN").unwrap_or(STARTING_FEN))?;
+
+    for symbol in symbols {
+        game.make_san_move(symbol)
+            .map_err(|e| format!("Invalid PGN: {e} (move {})", game.fullmove_number))?;
+    }
+
+    // A draw by agreement is kept in the Result tag (see write)
+    if marker.or(tag("Result")) == Some("1/2-1/2")
+        && !game.result.is_over()
+        && claimable_draw(game.halfmove_clock, &game.position_hashes).is_some()
+    {
+        game.claim_draw()
+            .map_err(|e| format!("Invalid PGN: {e} (move {})", game.fullmove_number))?;
+    }
+
+    Ok(PgnGame { tags, game })
+}
+
+fn tokenize(pgn: &str) -> Result<Vec<Token>, Box<dyn Error>> {
+    let mut tokens = Vec::new();
+    let mut chars = pgn.chars().peekable();
+    let mut line_start = true;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => {
+                line_start = true;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            // Escape mechanism (the rest of the line is ignored)
+            '%' if line_start => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+
+                continue;
+            }
+            // Rest of line comment
+            ';' => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+
+                line_start = true;
+                continue;
+            }
+            // Brace comment
+            '{' => {
+                if !chars.by_ref().any(|c| c == '}') {
+                    return Err("Invalid PGN: unterminated comment!".into());
+                }
+            }
+            '(' => tokens.push(Token::VariationStart),
+            ')' => tokens.push(Token::VariationEnd),
+            '[' => {
+                let mut tag = String::new();
+                let mut in_string = false;
+                let mut escaped = false;
+                let mut terminated = false;
+
+                for c in chars.by_ref() {
+                    if in_string && escaped {
+                        escaped = false;
+                    } else if in_string && c == '\\' {
+                        escaped = true;
+                    } else if c == '"' {
+                        in_string = !in_string;
+                    } else if c == ']' && !in_string {
+                        terminated = true;
+                        break;
+                    }
+
+                    tag.push(c);
+                }
+
+                if !terminated {
+                    return Err(format!("Invalid PGN: unterminated tag! ([{tag})").into());
+                }
+
+                tokens.push(parse_tag(&tag)?);
+            }
+            // Numeric annotation glyph
+            '$' => while chars.next_if(|c| c.is_ascii_digit()).is_some() {},
+            _ => {
+                let mut symbol = c.to_string();
+
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !"{}()[];".contains(*c))
+                {
+                    symbol.push(c);
+                }
+
+                // A symbol like "1.e4" contains the move number and the move
+                let symbol = match symbol.rfind('.') {
+                    Some(index)
+                        if index + 1 < symbol.len() && is_move_number(&symbol[..=index]) =>
+                    {
+                        symbol[index + 1..].to_string()
+                    }
+                    _ => symbol,
+                };
+
+                tokens.push(Token::Symbol(symbol));
+            }
+        }
+
+        line_start = false;
+    }
+
+    Ok(tokens)
+}
+
+fn parse_tag(tag: &str) -> Result<Token, Box<dyn Error>> {
+    let tag = tag.trim();
+    let (name, value) = tag
+        .split_once(char::is_whitespace)
+        .ok_or_else(|| format!("Invalid PGN: tag without value! ([{tag}])"))?;
+
+    let value = value.trim();
+
+    if value.len() < 2 || !value.starts_with('"') || !value.ends_with('"') {
+        return Err(format!("Invalid PGN: tag value has to be quoted! ([{tag}])").into());
+    }
+
+    let value = value[1..value.len() - 1]
+        .replace("\\\"", "\"")
+        .replace("\\\\", "\\");
+
+    Ok(Token::Tag(name.to_string(), value))
+}
+
+// Value of a tag of the Seven Tag Roster which is not known
+fn unknown_tag_value(name: &str) -> &'static str {
+    match name {
+        "Date" => "????.??.??",
+        "Result" => "*",
+        _ => "?",
+    }
+}
+
+fn format_tag(name: &str, value: &str) -> String {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+
+    format!("[{name} \"{value}\"]\n")
+}
+
+fn is_move_number(symbol: &str) -> bool {
+    let digits = symbol.trim_end_matches('.');
+
+    !digits.is_empty() && digits.len() < symbol.len() && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+fn is_game_termination_marker(symbol: &str) -> bool {
+    matches!(symbol, "1-0" | "0-1" | "1/2-1/2" | "*")
+}
+
+pub fn result_marker(result: &GameResult) -> &'static str {
+    match result {
+        GameResult::Ongoing => "*",
+        GameResult::Win(PieceColor::White, _) => "1-0",
+        GameResult::Win(PieceColor::Black, _) => "0-1",
+        GameResult::Draw(_) => "1/2-1/2",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_result::DrawReason;
+
+    #[test]
+    fn read_skips_comments_nags_and_variations() {
+        let pgn = r#"[Event "Casual game"]
+[Site "?"]
+[Date "2024.01.02"]
+[Round "1"]
+[White "Alice"]
+[Black "Bob \"The Rook\""]
+[Result "1-0"]
+[Annotator "Koala"]
+
+% Escaped line
+1. e4 {Best by test} e5 $1 2. Nf3 (2. f4 exf4 (2... d5) 3. Nf3) 2... Nc6 ; Rest of the line
+3.Bb5 a6 4. Ba4!? Nf6 5. O-O Be7 1-0
+"#;
+
+        let games = read(pgn).unwrap();
+        assert_eq!(games.len(), 1);
+
+        let pgn_game = &games[0];
+        assert_eq!(pgn_game.tag("Black"), Some("Bob \"The Rook\""));
+        assert_eq!(pgn_game.game.history.len(), 10);
+
+        let written = write(&pgn_game.game, &pgn_game.tags).unwrap();
+
+        assert_eq!(
+            written,
+            r#"[Event "Casual game"]
+[Site "?"]
+[Date "2024.01.02"]
+[Round "1"]
+[White "Alice"]
+[Black "Bob \"The Rook\""]
+[Result "1-0"]
+[Annotator "Koala"]
+
+1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 5. O-O Be7 1-0
+"#
+        );
+
+        // Reading the written game results in the same game
+        let reread = read(&written).unwrap().remove(0);
+        assert_eq!(reread.tags, pgn_game.tags);
+        assert_eq!(reread.game.history, pgn_game.game.history);
+        assert_eq!(write(&reread.game, &reread.tags).unwrap(), written);
+    }
+
+    #[test]
+    fn read_multiple_games() {
+        let pgn = "[Event \"1\"]\n\n1. e4 e5 *\n\n[Event \"2\"]\n\n1. d4 d5 2. c4 1/2-1/2\n";
+        let games = read(pgn).unwrap();
+
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].tag("Event"), Some("1"));
+        assert_eq!(games[1].game.history.len(), 3);
+    }
+
+    #[test]
+    fn read_draws() {
+        // A claimable draw is claimed
+        let pgn = "1. Nf3 Nf6 2. Ng1 Ng8 3. Nf3 Nf6 4. Ng1 Ng8 1/2-1/2";
+        let pgn_game = read(pgn).unwrap().remove(0);
+        assert_eq!(
+            pgn_game.game.result,
+            GameResult::Draw(DrawReason::ThreefoldRepetition)
+        );
+
+        // A draw by agreement is kept in the Result tag
+        let pgn = "[Result \"1/2-1/2\"]\n\n1. d4 d5 2. c4 1/2-1/2";
+        let pgn_game = read(pgn).unwrap().remove(0);
+        assert_eq!(pgn_game.game.result, GameResult::Ongoing);
+        assert!(write(&pgn_game.game, &pgn_game.tags)
+            .unwrap()
+            .ends_with("2. c4 1/2-1/2\n"));
+    }
+
+    #[test]
+    fn read_errors() {
+        for (pgn, expected_error) in [
+            ("1. e4 {Unterminated", "unterminated comment"),
+            ("1. e4 ) e5", "unexpected ')'"),
+            ("[Event \"Unterminated\"", "unterminated tag"),
+            ("[Event Unquoted]", "tag value has to be quoted"),
+            ("1. e4 e4", "(move 1)"),
+        ] {
+            let error = read(pgn).err().map(|e| e.to_string()).unwrap_or_default();
+
+            assert!(error.contains(expected_error), "{pgn}: {error}");
+        }
+    }
+
+    #[test]
+    fn write_invalid_starting_fen() {
+        let mut game = Game::new();
+        game.starting_fen = "8/8/8/8/8/8/8/8 w - - 0 1".to_string();
+
+        assert!(write(&game, &[]).is_err());
+    }
+
+    #[test]
+    fn write_unknown_tags() {
+        let written = write(&Game::new(), &[]).unwrap();
+
+        assert!(written.starts_with(
+            "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n\
+             [White \"?\"]\n[Black \"?\"]\n[Result \"*\"]\n\n*\n"
+        ));
+    }
+
+    #[test]
+    fn write_setup_position() {
+        let fen = "4k3/8/8/8/8/8/4P3/4K2R b K - 3 40";
+        let mut game = Game::from_fen(fen).unwrap();
+        game.make_san_move("Kd7").unwrap();
+        game.make_san_move("O-O").unwrap();
+
+        let written = write(&game, &[]).unwrap();
+        assert!(written.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K2R b K - 3 40\"]\n"));
+        assert!(written.ends_with("\n40... Kd7 41. O-O *\n"));
+
+        let reread = read(&written).unwrap().remove(0);
+        assert_eq!(reread.game.starting_fen, fen);
+        assert_eq!(reread.game.history, game.history);
+    }
+
+    #[test]
+    fn write_whole_line() {
+        let mut game = Game::new();
+
+        for r#move in ["f3", "e5", "g4", "Qh4#"] {
+            game.make_san_move(r#move).unwrap();
+        }
+
+        game.jump_to_ply(1).unwrap();
+
+        let written = write(&game, &[]).unwrap();
+        assert!(written.contains("[Result \"0-1\"]\n"));
+        assert!(written.ends_with("\n1. f3 e5 2. g4 Qh4# 0-1\n"));
+    }
+
+    #[test]
+    fn write_wraps_long_movetext() {
+        let mut game = Game::new();
+
+        // Ruy Lopez, Breyer variation
+        for r#move in [
+            "e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Ba4", "Nf6", "O-O", "Be7", "Re1", "b5", "Bb3",
+            "d6", "c3", "O-O", "h3", "Nb8", "d4", "Nbd7", "Nbd2", "Bb7", "Bc2", "Re8", "Nf1",
+            "Bf8", "Ng3", "g6",
+        ] {
+            game.make_san_move(r#move).unwrap();
+        }
+
+        let written = write(&game, &[]).unwrap();
+
+        let (_, movetext) = written.split_once("\n\n").unwrap();
+        assert!(movetext.lines().count() > 1);
+        assert!(written.lines().all(|line| line.len() <= MAX_LINE_LENGTH));
+        assert_eq!(read(&written).unwrap().remove(0).game.history, game.history);
+    }
+}
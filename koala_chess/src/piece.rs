@@ -12,12 +12,30 @@ use std::{
     sync::{LazyLock, Mutex},
 };
 
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
 pub enum PieceColor {
     White,
     Black,
 }
 
+impl PieceColor {
+    pub fn opposite(&self) -> PieceColor {
+        match self {
+            PieceColor::White => PieceColor::Black,
+            PieceColor::Black => PieceColor::White,
+        }
+    }
+
+    // Index for lookup tables
+    pub fn index(&self) -> usize {
+        match self {
+            PieceColor::White => 0,
+            PieceColor::Black => 1,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
 pub enum PieceKind {
     Pawn,
     Knight,
@@ -27,10 +45,34 @@ pub enum PieceKind {
     King,
 }
 
+impl PieceKind {
+    pub const ALL: [PieceKind; 6] = [
+        PieceKind::Pawn,
+        PieceKind::Knight,
+        PieceKind::Bishop,
+        PieceKind::Rook,
+        PieceKind::Queen,
+        PieceKind::King,
+    ];
+
+    // Index for lookup tables
+    pub fn index(&self) -> usize {
+        match self {
+            PieceKind::Pawn => 0,
+            PieceKind::Knight => 1,
+            PieceKind::Bishop => 2,
+            PieceKind::Rook => 3,
+            PieceKind::Queen => 4,
+            PieceKind::King => 5,
+        }
+    }
+}
+
 static ATLAS_SHADER: LazyLock<Mutex<Option<Shader>>> = LazyLock::new(|| Mutex::new(None));
 static mut VERTEX_BUFFER_OBJECT: gl::types::GLuint = 0;
 static mut TEXTURE: gl::types::GLuint = 0;
 
+#[derive(Clone)]
 pub struct Piece {
     pub x: f32,
     pub y: f32,
@@ -38,12 +80,13 @@ pub struct Piece {
     pub height: f32,
     #[allow(dead_code)]
     pub rotation: f32,
-    #[allow(dead_code)]
+    // 0.0 = invisible, 1.0 = opaque
+    pub alpha: f32,
     pub color: PieceColor,
-    #[allow(dead_code)]
     pub kind: PieceKind,
     pub board_x: u8,
     pub board_y: u8,
+    pub has_moved: bool,
     piece_x: u8,
     piece_y: u8,
 }
@@ -53,20 +96,7 @@ impl Piece {
     pub const TEXTURE_SIZE: i32 = 253;
 
     pub fn new(color: PieceColor, kind: PieceKind, board_x: u8, board_y: u8) -> Piece {
-        let (piece_x, piece_y) = match (&color, &kind) {
-            (PieceColor::White, PieceKind::Pawn) => (0, 2),
-            (PieceColor::White, PieceKind::Knight) => (2, 2),
-            (PieceColor::White, PieceKind::Bishop) => (0, 1),
-            (PieceColor::White, PieceKind::Rook) => (2, 1),
-            (PieceColor::White, PieceKind::Queen) => (0, 0),
-            (PieceColor::White, PieceKind::King) => (2, 0),
-            (PieceColor::Black, PieceKind::Pawn) => (1, 2),
-            (PieceColor::Black, PieceKind::Knight) => (3, 2),
-            (PieceColor::Black, PieceKind::Bishop) => (1, 1),
-            (PieceColor::Black, PieceKind::Rook) => (3, 1),
-            (PieceColor::Black, PieceKind::Queen) => (1, 0),
-            (PieceColor::Black, PieceKind::King) => (3, 0),
-        };
+        let (piece_x, piece_y) = atlas_coordinates(color, kind);
 
         Piece {
             x: 0.0,
@@ -74,10 +104,12 @@ impl Piece {
             width: 0.0,
             height: 0.0,
             rotation: 0.0,
+            alpha: 1.0,
             color,
             kind,
             board_x,
             board_y,
+            has_moved: false,
             piece_x,
             piece_y,
         }
@@ -221,8 +253,9 @@ impl Piece {
 
             model = translate(model, Vec3::new_xyz(x_translation, y_translation, 0.0));
 
-            // Reset piece rotation, so that the piece is always facing upwards
-            model = rotate_z(model, 0.0);
+            // Undo the board rotation, so that the piece is always facing upwards (also while the
+            // board is being flipped)
+            model = rotate_z(model, -board.rotation);
 
             model = translate(model, Vec3::new_xyz(-x_translation, -y_translation, 0.0));
         }
@@ -236,6 +269,7 @@ impl Piece {
         )?;
         atlas_shader.set_float("piece_x\0", self.piece_x as gl::types::GLfloat)?;
         atlas_shader.set_float("piece_y\0", self.piece_y as gl::types::GLfloat)?;
+        atlas_shader.set_float("alpha\0", self.alpha)?;
 
         // Draw elements
         unsafe {
@@ -245,3 +279,20 @@ impl Piece {
         Ok(())
     }
 }
+
+fn atlas_coordinates(color: PieceColor, kind: PieceKind) -> (u8, u8) {
+    match (color, kind) {
+        (PieceColor::White, PieceKind::Pawn) => (0, 2),
+        (PieceColor::White, PieceKind::Knight) => (2, 2),
+        (PieceColor::White, PieceKind::Bishop) => (0, 1),
+        (PieceColor::White, PieceKind::Rook) => (2, 1),
+        (PieceColor::White, PieceKind::Queen) => (0, 0),
+        (PieceColor::White, PieceKind::King) => (2, 0),
+        (PieceColor::Black, PieceKind::Pawn) => (1, 2),
+        (PieceColor::Black, PieceKind::Knight) => (3, 2),
+        (PieceColor::Black, PieceKind::Bishop) => (1, 1),
+        (PieceColor::Black, PieceKind::Rook) => (3, 1),
+        (PieceColor::Black, PieceKind::Queen) => (1, 0),
+        (PieceColor::Black, PieceKind::King) => (3, 0),
+    }
+}
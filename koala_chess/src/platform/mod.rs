@@ -3,3 +3,14 @@ pub mod windows;
 
 #[cfg(target_family = "unix")]
 pub mod unix;
+
+use std::time::Duration;
+
+// Time since an unspecified point in the past, which is not affected by changes of the system time
+pub fn monotonic_time() -> Duration {
+    #[cfg(target_family = "windows")]
+    return windows::monotonic_time();
+
+    #[cfg(target_family = "unix")]
+    return unix::monotonic_time();
+}
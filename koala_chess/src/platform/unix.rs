@@ -1,14 +1,17 @@
 use crate::game::Game;
+use crate::input::{InputEvent, Key, MouseButton};
+use crate::platform;
 use crate::renderer::open_gl;
 use logger::*;
-use std::os::raw::{c_int, c_uint};
+use std::os::raw::{c_char, c_int, c_long, c_uint};
 use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
 use std::{error::Error, mem::MaybeUninit};
 use std::{
     ffi::{c_void, CStr, CString},
     ptr::addr_of_mut,
 };
-use x11::xlib;
+use x11::{keysym, xlib};
 
 static ASPECT_RATIO: LazyLock<Mutex<f32>> = LazyLock::new(|| Mutex::new(1.0));
 
@@ -167,7 +170,15 @@ pub fn create_window() -> (*mut xlib::Display, glx::types::Window) {
                 (*visual_info).visual as *mut xlib::Visual,
                 xlib::AllocNone,
             ));
-            addr_of_mut!((*ptr).event_mask).write(xlib::ExposureMask);
+            addr_of_mut!((*ptr).event_mask).write(
+                xlib::ExposureMask
+                    | xlib::ButtonPressMask
+                    | xlib::ButtonReleaseMask
+                    | xlib::PointerMotionMask
+                    | xlib::KeyPressMask
+                    | xlib::KeyReleaseMask
+                    | xlib::StructureNotifyMask,
+            );
 
             attributes_uninit.assume_init()
         };
@@ -243,42 +254,68 @@ pub fn r#loop(display: *mut xlib::Display, window: u64, game: &mut Game) {
             event_uninit.assume_init()
         };
 
-        let mut last_time = libc::timespec {
-            tv_sec: 0,
-            tv_nsec: 0,
-        };
+        let mut last_time = platform::monotonic_time();
 
-        if libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut last_time) == -1 {
-            fatal!("clock_gettime failed!");
-        }
+        let mut is_fullscreen = false;
 
         'outer: loop {
             // Window loop
             while { xlib::XPending(display) } > 0 {
                 xlib::XNextEvent(display, &mut event);
 
-                if event.type_ == xlib::Expose {
-                    let mut attributes = {
-                        let attributes_uninit = MaybeUninit::uninit();
+                // The window manager resizes the window (e.g. when switching to fullscreen)
+                if event.type_ == xlib::Expose || event.type_ == xlib::ConfigureNotify {
+                    resize(display, window, game);
+                }
+
+                if event.type_ == xlib::ButtonPress || event.type_ == xlib::ButtonRelease {
+                    let xbutton = xlib::XButtonEvent::from(event);
 
-                        attributes_uninit.assume_init()
+                    // Reference: https://tronche.com/gui/x/xlib/events/keyboard-pointer/keyboard-pointer.html
+                    let button = match xbutton.button {
+                        xlib::Button1 => Some(MouseButton::Left),
+                        xlib::Button2 => Some(MouseButton::Middle),
+                        xlib::Button3 => Some(MouseButton::Right),
+                        // Mouse wheel and additional buttons
+                        _ => None,
                     };
-                    xlib::XGetWindowAttributes(display, window, &mut attributes);
-                    let width = attributes.width;
-                    let height = attributes.height;
-                    let aspect_ratio = width as f32 / height as f32;
-                    info!(
-                        "Expose: width: {} / height: {} / aspect_ratio: {}",
-                        width, height, aspect_ratio
-                    );
-
-                    *ASPECT_RATIO
-                        .lock()
-                        .unwrap_or_else(|e| fatal!("Could not lock aspect ratio mutex! ({})", e)) =
-                        aspect_ratio;
-
-                    // Set viewport
-                    gl::Viewport(0, 0, width, height);
+
+                    if let Some(button) = button {
+                        game.handle_input(if event.type_ == xlib::ButtonPress {
+                            InputEvent::MouseButtonPressed {
+                                button,
+                                x: xbutton.x,
+                                y: xbutton.y,
+                            }
+                        } else {
+                            InputEvent::MouseButtonReleased {
+                                button,
+                                x: xbutton.x,
+                                y: xbutton.y,
+                            }
+                        });
+                    }
+                }
+
+                if event.type_ == xlib::MotionNotify {
+                    let xmotion = xlib::XMotionEvent::from(event);
+
+                    game.handle_input(InputEvent::MouseMoved {
+                        x: xmotion.x,
+                        y: xmotion.y,
+                    });
+                }
+
+                if event.type_ == xlib::KeyPress || event.type_ == xlib::KeyRelease {
+                    let mut xkey = xlib::XKeyEvent::from(event);
+
+                    if let Some(key) = translate_key(&mut xkey) {
+                        game.handle_input(if event.type_ == xlib::KeyPress {
+                            InputEvent::KeyPressed { key }
+                        } else {
+                            InputEvent::KeyReleased { key }
+                        });
+                    }
                 }
 
                 if let xlib::ClientMessage = event.get_type() {
@@ -294,33 +331,39 @@ pub fn r#loop(display: *mut xlib::Display, window: u64, game: &mut Game) {
                 }
             }
 
+            // Quit via the key binding
+            if game.quit {
+                break 'outer;
+            }
+
+            // Toggle fullscreen via the key binding (the resize follows as a ConfigureNotify event)
+            if game.fullscreen != is_fullscreen {
+                is_fullscreen = game.fullscreen;
+                set_fullscreen(display, window, is_fullscreen);
+            }
+
+            // Frame time (time since the last frame)
+            let current_time = platform::monotonic_time();
+            let delta_time = current_time - last_time;
+            last_time = current_time;
+
+            // Update game
+            game.update();
+
             // Rendering
             let aspect_ratio = *ASPECT_RATIO
                 .lock()
                 .unwrap_or_else(|e| fatal!("Could not lock aspect ratio mutex! ({})", e));
 
             // Draw game
-            if let Err(e) = game.draw(aspect_ratio) {
+            if let Err(e) = game.draw(aspect_ratio, delta_time) {
                 error!("{}", e);
             }
 
             glx::SwapBuffers(display as *mut glx::types::Display, window);
 
             // Metrics
-            let mut end_time = libc::timespec {
-                tv_sec: 0,
-                tv_nsec: 0,
-            };
-
-            if libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut end_time) == -1 {
-                fatal!("clock_gettime failed!");
-            }
-
-            // ms = end time - last time
-            let elapsed_milliseconds = end_time.tv_sec as f64 * 1_000f64
-                + end_time.tv_nsec as f64 * 1e-6
-                - last_time.tv_sec as f64 * 1_000f64
-                - last_time.tv_nsec as f64 * 1e-6;
+            let elapsed_milliseconds = delta_time.as_secs_f64() * 1_000f64;
 
             // 1/s = 1000 / elapsed milliseconds
             let frames_per_second = 1_000f64 / elapsed_milliseconds;
@@ -329,10 +372,152 @@ pub fn r#loop(display: *mut xlib::Display, window: u64, game: &mut Game) {
                 "frames per second: {} / frame time: {}ms",
                 frames_per_second, elapsed_milliseconds
             );
+        }
+    }
+}
+
+// Adapts the viewport and the game to the current window size
+unsafe fn resize(display: *mut xlib::Display, window: u64, game: &mut Game) {
+    let mut attributes = {
+        let attributes_uninit = MaybeUninit::uninit();
+
+        attributes_uninit.assume_init()
+    };
+    xlib::XGetWindowAttributes(display, window, &mut attributes);
+    let width = attributes.width;
+    let height = attributes.height;
+    let aspect_ratio = width as f32 / height as f32;
+    info!(
+        "Resize: width: {} / height: {} / aspect_ratio: {}",
+        width, height, aspect_ratio
+    );
+
+    *ASPECT_RATIO
+        .lock()
+        .unwrap_or_else(|e| fatal!("Could not lock aspect ratio mutex! ({})", e)) = aspect_ratio;
+
+    // Set viewport
+    gl::Viewport(0, 0, width, height);
+
+    game.handle_input(InputEvent::Resized { width, height });
+}
+
+// Switches between the window and fullscreen. The window is managed by the window manager (the
+// override_redirect attribute is not in the valuemask), so it is asked to change the window state
+// Reference: https://specifications.freedesktop.org/wm-spec/latest/ar01s05.html#id-1.6.8
+unsafe fn set_fullscreen(display: *mut xlib::Display, window: u64, fullscreen: bool) {
+    info!(
+        "{} fullscreen",
+        if fullscreen { "Entering" } else { "Leaving" }
+    );
+
+    let net_wm_state_str =
+        CString::new("_NET_WM_STATE").unwrap_or_else(|_| fatal!("Could not create CString!"));
+    let net_wm_state_fullscreen_str = CString::new("_NET_WM_STATE_FULLSCREEN")
+        .unwrap_or_else(|_| fatal!("Could not create CString!"));
 
-            last_time = end_time;
+    let net_wm_state = xlib::XInternAtom(
+        display,                   // display
+        net_wm_state_str.as_ptr(), // atom_name
+        xlib::False,               // only_if_exists
+    );
+    let net_wm_state_fullscreen = xlib::XInternAtom(
+        display,                              // display
+        net_wm_state_fullscreen_str.as_ptr(), // atom_name
+        xlib::False,                          // only_if_exists
+    );
+
+    let mut data = xlib::ClientMessageData::new();
+    let longs = data.as_longs_mut();
+    // _NET_WM_STATE_ADD (1) or _NET_WM_STATE_REMOVE (0)
+    longs[0] = fullscreen as c_long;
+    longs[1] = net_wm_state_fullscreen as c_long;
+    // No second property
+    longs[2] = 0;
+    // Source indication: normal application
+    longs[3] = 1;
+
+    let mut event = xlib::XEvent::from(xlib::XClientMessageEvent {
+        type_: xlib::ClientMessage,
+        serial: 0,
+        send_event: xlib::True,
+        display,
+        window,
+        message_type: net_wm_state,
+        format: 32,
+        data,
+    });
+
+    // Reference: https://tronche.com/gui/x/xlib/event-handling/XSendEvent.html
+    xlib::XSendEvent(
+        display,                                                       // display
+        xlib::XDefaultRootWindow(display),                             // w
+        xlib::False,                                                   // propagate
+        xlib::SubstructureRedirectMask | xlib::SubstructureNotifyMask, // event_mask
+        &mut event,                                                    // event_send
+    );
+    xlib::XFlush(display);
+}
+
+// Translates a key event into a named key or the typed character
+unsafe fn translate_key(xkey: &mut xlib::XKeyEvent) -> Option<Key> {
+    let mut buffer: [c_char; 8] = [0; 8];
+    let mut keysym = 0;
+
+    // Translate the key into its keysym and the typed characters (Latin-1)
+    // Reference: https://tronche.com/gui/x/xlib/utilities/XLookupString.html
+    let length = xlib::XLookupString(
+        xkey,
+        buffer.as_mut_ptr(),
+        buffer.len() as c_int,
+        &mut keysym,
+        std::ptr::null_mut(),
+    );
+
+    let key = match keysym as c_uint {
+        keysym::XK_Return | keysym::XK_KP_Enter => Key::Enter,
+        keysym::XK_Escape => Key::Escape,
+        keysym::XK_BackSpace => Key::Backspace,
+        // Shift + Tab
+        keysym::XK_Tab | keysym::XK_ISO_Left_Tab => Key::Tab,
+        keysym::XK_Delete => Key::Delete,
+        keysym::XK_Left => Key::Left,
+        keysym::XK_Right => Key::Right,
+        keysym::XK_Up => Key::Up,
+        keysym::XK_Down => Key::Down,
+        keysym::XK_Home => Key::Home,
+        keysym::XK_End => Key::End,
+        keysym::XK_Prior => Key::PageUp,
+        keysym::XK_Next => Key::PageDown,
+        keysym::XK_F1..=keysym::XK_F12 => {
+            Key::Function((keysym as c_uint - keysym::XK_F1 + 1) as u8)
+        }
+        _ if length == 1 => {
+            let c = buffer[0] as u8 as char;
+
+            if c.is_control() {
+                return None;
+            }
+
+            Key::Character(c)
         }
+        _ => return None,
+    };
+
+    Some(key)
+}
+
+pub fn monotonic_time() -> Duration {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+
+    if unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut time) } == -1 {
+        fatal!("clock_gettime failed!");
     }
+
+    Duration::new(time.tv_sec as u64, time.tv_nsec as u32)
 }
 
 fn initialize_open_gl(
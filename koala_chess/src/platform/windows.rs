@@ -1,4 +1,6 @@
 use crate::game::Game;
+use crate::input::{InputEvent, Key, MouseButton};
+use crate::platform;
 use crate::renderer::open_gl;
 use logger::*;
 use std::error::Error;
@@ -10,6 +12,7 @@ use std::sync::{
     atomic::{AtomicBool, Ordering},
     Mutex,
 };
+use std::time::Duration;
 use winapi::um::wingdi::wglDeleteContext;
 use winapi::{
     shared::{
@@ -26,10 +29,17 @@ use winapi::{
         },
         winnt::LARGE_INTEGER,
         winuser::{
-            CreateWindowExW, DefWindowProcW, DispatchMessageW, GetClientRect, GetDC, PeekMessageW,
-            PostQuitMessage, RegisterClassW, ReleaseDC, TranslateMessage, CS_HREDRAW, CS_OWNDC,
-            CS_VREDRAW, CW_USEDEFAULT, MSG, PM_REMOVE, WM_CLOSE, WM_DESTROY, WM_QUIT, WM_SIZE,
-            WNDCLASSW, WS_OVERLAPPEDWINDOW, WS_VISIBLE,
+            CreateWindowExW, DefWindowProcW, DispatchMessageW, GetClientRect, GetDC,
+            GetMonitorInfoW, GetWindowLongW, GetWindowPlacement, MonitorFromWindow, PeekMessageW,
+            PostQuitMessage, RegisterClassW, ReleaseCapture, ReleaseDC, SetCapture, SetWindowLongW,
+            SetWindowPlacement, SetWindowPos, TranslateMessage, CS_HREDRAW, CS_OWNDC, CS_VREDRAW,
+            CW_USEDEFAULT, GWL_STYLE, HWND_TOP, MONITORINFO, MONITOR_DEFAULTTOPRIMARY, MSG,
+            PM_REMOVE, SWP_FRAMECHANGED, SWP_NOMOVE, SWP_NOOWNERZORDER, SWP_NOSIZE, SWP_NOZORDER,
+            VK_BACK, VK_DELETE, VK_DOWN, VK_END, VK_ESCAPE, VK_F1, VK_F12, VK_HOME, VK_LEFT,
+            VK_NEXT, VK_PRIOR, VK_RETURN, VK_RIGHT, VK_TAB, VK_UP, WINDOWPLACEMENT, WM_CHAR,
+            WM_CLOSE, WM_DESTROY, WM_KEYDOWN, WM_KEYUP, WM_LBUTTONDOWN, WM_LBUTTONUP,
+            WM_MBUTTONDOWN, WM_MBUTTONUP, WM_MOUSEMOVE, WM_QUIT, WM_RBUTTONDOWN, WM_RBUTTONUP,
+            WM_SIZE, WM_SYSKEYDOWN, WM_SYSKEYUP, WNDCLASSW, WS_OVERLAPPEDWINDOW, WS_VISIBLE,
         },
     },
 };
@@ -43,6 +53,8 @@ static OPEN_GL_MODULE: LazyLock<Mutex<ModuleHandle>> =
     LazyLock::new(|| Mutex::new(ModuleHandle(std::ptr::null_mut())));
 static INITIALIZED_OPEN_GL: LazyLock<AtomicBool> = LazyLock::new(|| AtomicBool::new(false));
 static ASPECT_RATIO: LazyLock<Mutex<f32>> = LazyLock::new(|| Mutex::new(1.0));
+// window_proc has no access to the game, so the input events are queued and handled by the loop
+static INPUT_EVENTS: LazyLock<Mutex<Vec<InputEvent>>> = LazyLock::new(|| Mutex::new(Vec::new()));
 
 pub fn create_window() -> HWND {
     // Create window class name
@@ -119,15 +131,13 @@ pub fn create_window() -> HWND {
 pub fn r#loop(window: HWND, game: &mut Game) {
     let device_context = unsafe { GetDC(window) };
 
-    // The frequency of the performance counter is fixed at system boot and is consistent across all processors
-    let mut performance_frequency = LARGE_INTEGER::default();
-    unsafe { QueryPerformanceFrequency(&mut performance_frequency) };
-
-    let mut last_performance_counter = LARGE_INTEGER::default();
-    unsafe { QueryPerformanceCounter(&mut last_performance_counter) };
+    let mut last_time = platform::monotonic_time();
 
     let mut running = true;
 
+    // Placement of the window before it has been switched to fullscreen
+    let mut windowed_placement = None;
+
     while running {
         let mut message = MSG::default();
 
@@ -146,37 +156,78 @@ pub fn r#loop(window: HWND, game: &mut Game) {
             }
         }
 
+        // Input
+        let input_events = std::mem::take(
+            &mut *INPUT_EVENTS
+                .lock()
+                .unwrap_or_else(|e| fatal!("Could not lock input events mutex! ({})", e)),
+        );
+
+        for input_event in input_events {
+            game.handle_input(input_event);
+        }
+
+        // Quit via the key binding
+        if game.quit {
+            break;
+        }
+
+        // Toggle fullscreen via the key binding
+        if game.fullscreen != windowed_placement.is_some() {
+            set_fullscreen(window, &mut windowed_placement);
+
+            // If the switch failed, it is not retried every frame (until the next key press)
+            game.fullscreen = windowed_placement.is_some();
+        }
+
+        // Frame time (time since the last frame)
+        let current_time = platform::monotonic_time();
+        let delta_time = current_time - last_time;
+        last_time = current_time;
+
+        // Update game
+        game.update();
+
         // Rendering
         let aspect_ratio = *ASPECT_RATIO
             .lock()
             .unwrap_or_else(|e| fatal!("Could not lock aspect ratio mutex! ({})", e));
 
         // Draw game
-        if let Err(e) = game.draw(aspect_ratio) {
+        if let Err(e) = game.draw(aspect_ratio, delta_time) {
             error!("{}", e);
         }
 
         unsafe { SwapBuffers(device_context) };
 
         // Metrics
-        let mut end_performance_counter = LARGE_INTEGER::default();
-        unsafe { QueryPerformanceCounter(&mut end_performance_counter) };
+        let elapsed_milliseconds = delta_time.as_secs_f64() * 1_000f64;
 
-        let elapsed_performance_counter =
-            unsafe { end_performance_counter.QuadPart() - last_performance_counter.QuadPart() };
+        // 1/s = 1000 / elapsed milliseconds
+        let frames_per_second = 1_000f64 / elapsed_milliseconds;
 
-        // ms = 1000 * counter / (counter / s) = 1000 * counter * (s / counter)
-        let elapsed_milliseconds = 1_000f64 * elapsed_performance_counter as f64
-            / unsafe { *performance_frequency.QuadPart() as f64 };
+        println!("frames per second: {frames_per_second} / frame time: {elapsed_milliseconds}ms");
+    }
+}
 
-        // 1/s = (counter / s) / counter
-        let frames_per_second = unsafe { *performance_frequency.QuadPart() as f64 }
-            / elapsed_performance_counter as f64;
+pub fn monotonic_time() -> Duration {
+    // The frequency of the performance counter is fixed at system boot and is consistent across all processors
+    let mut performance_frequency = LARGE_INTEGER::default();
+    unsafe { QueryPerformanceFrequency(&mut performance_frequency) };
 
-        println!("frames per second: {frames_per_second} / frame time: {elapsed_milliseconds}ms");
+    let mut performance_counter = LARGE_INTEGER::default();
+    unsafe { QueryPerformanceCounter(&mut performance_counter) };
 
-        last_performance_counter = end_performance_counter;
-    }
+    let (counter, frequency) = unsafe {
+        (
+            *performance_counter.QuadPart() as u64,
+            *performance_frequency.QuadPart() as u64,
+        )
+    };
+
+    // s = counter / (counter / s). The remainder is converted separately to avoid an overflow
+    Duration::from_secs(counter / frequency)
+        + Duration::from_nanos((counter % frequency) * 1_000_000_000 / frequency)
 }
 
 fn initialize_open_gl(window: HWND) {
@@ -426,6 +477,60 @@ unsafe extern "system" fn window_proc(
                 // Set viewport
                 gl::Viewport(0, 0, width, height);
             }
+
+            push_input_event(InputEvent::Resized { width, height });
+        }
+        WM_LBUTTONDOWN | WM_MBUTTONDOWN | WM_RBUTTONDOWN => {
+            // Keep receiving mouse events while a button is pressed (e.g. when dragging outside of the window)
+            SetCapture(window);
+
+            push_input_event(InputEvent::MouseButtonPressed {
+                button: mouse_button(message),
+                x: x_from_l_param(l_param),
+                y: y_from_l_param(l_param),
+            });
+        }
+        WM_LBUTTONUP | WM_MBUTTONUP | WM_RBUTTONUP => {
+            ReleaseCapture();
+
+            push_input_event(InputEvent::MouseButtonReleased {
+                button: mouse_button(message),
+                x: x_from_l_param(l_param),
+                y: y_from_l_param(l_param),
+            });
+        }
+        WM_MOUSEMOVE => {
+            push_input_event(InputEvent::MouseMoved {
+                x: x_from_l_param(l_param),
+                y: y_from_l_param(l_param),
+            });
+        }
+        WM_CHAR => {
+            // Printable characters (TranslateMessage creates WM_CHAR from WM_KEYDOWN)
+            if let Some(c) = char::from_u32(w_param as u32).filter(|c| !c.is_control()) {
+                push_input_event(InputEvent::KeyPressed {
+                    key: Key::Character(c),
+                });
+            }
+        }
+        WM_KEYDOWN | WM_SYSKEYDOWN => {
+            // Printable characters follow as WM_CHAR
+            if let Some(key) = named_key(w_param) {
+                push_input_event(InputEvent::KeyPressed { key });
+            }
+        }
+        WM_KEYUP | WM_SYSKEYUP => {
+            // There is no WM_CHAR for released keys, so letters and digits are derived from the
+            // virtual-key code (without the modifiers)
+            let key = named_key(w_param).or_else(|| {
+                char::from_u32(w_param as u32)
+                    .filter(char::is_ascii_alphanumeric)
+                    .map(|c| Key::Character(c.to_ascii_lowercase()))
+            });
+
+            if let Some(key) = key {
+                push_input_event(InputEvent::KeyReleased { key });
+            }
         }
         WM_DESTROY => {
             info!("window_proc: WM_DESTROY");
@@ -440,3 +545,117 @@ unsafe extern "system" fn window_proc(
 
     DefWindowProcW(window, message, w_param, l_param)
 }
+
+fn push_input_event(input_event: InputEvent) {
+    INPUT_EVENTS
+        .lock()
+        .unwrap_or_else(|e| fatal!("Could not lock input events mutex! ({})", e))
+        .push(input_event);
+}
+
+// Reference: https://learn.microsoft.com/en-us/windows/win32/inputdev/virtual-key-codes
+fn named_key(w_param: WPARAM) -> Option<Key> {
+    let key = match w_param as i32 {
+        VK_RETURN => Key::Enter,
+        VK_ESCAPE => Key::Escape,
+        VK_BACK => Key::Backspace,
+        VK_TAB => Key::Tab,
+        VK_DELETE => Key::Delete,
+        VK_LEFT => Key::Left,
+        VK_RIGHT => Key::Right,
+        VK_UP => Key::Up,
+        VK_DOWN => Key::Down,
+        VK_HOME => Key::Home,
+        VK_END => Key::End,
+        VK_PRIOR => Key::PageUp,
+        VK_NEXT => Key::PageDown,
+        VK_F1..=VK_F12 => Key::Function((w_param as i32 - VK_F1 + 1) as u8),
+        _ => return None,
+    };
+
+    Some(key)
+}
+
+// Switches between the window and fullscreen (a borderless window which covers the monitor)
+// Reference: https://devblogs.microsoft.com/oldnewthing/20100412-00/?p=14353
+fn set_fullscreen(window: HWND, windowed_placement: &mut Option<WINDOWPLACEMENT>) {
+    unsafe {
+        let style = GetWindowLongW(window, GWL_STYLE);
+
+        match windowed_placement.take() {
+            Some(placement) => {
+                info!("Leaving fullscreen");
+
+                SetWindowLongW(window, GWL_STYLE, style | WS_OVERLAPPEDWINDOW as i32);
+                SetWindowPlacement(window, &placement);
+                SetWindowPos(
+                    window,
+                    std::ptr::null_mut(),
+                    0,
+                    0,
+                    0,
+                    0,
+                    SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_NOOWNERZORDER | SWP_FRAMECHANGED,
+                );
+            }
+            None => {
+                info!("Entering fullscreen");
+
+                let mut placement = WINDOWPLACEMENT {
+                    length: std::mem::size_of::<WINDOWPLACEMENT>() as UINT,
+                    ..Default::default()
+                };
+                let mut monitor_info = MONITORINFO {
+                    cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+                    ..Default::default()
+                };
+
+                if GetWindowPlacement(window, &mut placement) == 0
+                    || GetMonitorInfoW(
+                        MonitorFromWindow(window, MONITOR_DEFAULTTOPRIMARY),
+                        &mut monitor_info,
+                    ) == 0
+                {
+                    error!(
+                        "Could not enter fullscreen! (os error: {})",
+                        io::Error::last_os_error()
+                    );
+                    return;
+                }
+
+                let monitor = monitor_info.rcMonitor;
+
+                SetWindowLongW(window, GWL_STYLE, style & !(WS_OVERLAPPEDWINDOW as i32));
+                SetWindowPos(
+                    window,
+                    HWND_TOP,
+                    monitor.left,
+                    monitor.top,
+                    monitor.right - monitor.left,
+                    monitor.bottom - monitor.top,
+                    SWP_NOOWNERZORDER | SWP_FRAMECHANGED,
+                );
+
+                *windowed_placement = Some(placement);
+            }
+        }
+    }
+}
+
+fn mouse_button(message: UINT) -> MouseButton {
+    match message {
+        WM_LBUTTONDOWN | WM_LBUTTONUP => MouseButton::Left,
+        WM_MBUTTONDOWN | WM_MBUTTONUP => MouseButton::Middle,
+        _ => MouseButton::Right,
+    }
+}
+
+// Equivalent of GET_X_LPARAM (the coordinates are signed when the mouse is captured)
+fn x_from_l_param(l_param: LPARAM) -> i32 {
+    (l_param & 0xffff) as i16 as i32
+}
+
+// Equivalent of GET_Y_LPARAM (the coordinates are signed when the mouse is captured)
+fn y_from_l_param(l_param: LPARAM) -> i32 {
+    ((l_param >> 16) & 0xffff) as i16 as i32
+}
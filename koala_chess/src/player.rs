@@ -1,5 +1,22 @@
 use crate::piece::PieceColor;
 
-pub struct _Player {
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum PlayerKind {
+    Human,
+    // Built-in engine
+    Engine,
+    // Engine which is running as a subprocess (see Game::external_engine)
+    ExternalEngine,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Player {
     pub piece_color: PieceColor,
+    pub kind: PlayerKind,
+}
+
+impl Player {
+    pub fn new(piece_color: PieceColor, kind: PlayerKind) -> Player {
+        Player { piece_color, kind }
+    }
 }
@@ -21,3 +21,15 @@ pub fn orthogonal_projection(
 
     projection
 }
+
+// Maps normalized device coordinates back to world coordinates
+pub fn inverse_orthogonal_projection(
+    projection: &Mat4,
+    x: gl::types::GLfloat,
+    y: gl::types::GLfloat,
+) -> (gl::types::GLfloat, gl::types::GLfloat) {
+    (
+        (x - projection[3][0]) / projection[0][0],
+        (y - projection[3][1]) / projection[1][1],
+    )
+}
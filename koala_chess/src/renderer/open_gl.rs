@@ -35,6 +35,7 @@ pub fn initialize_open_gl_addresses(get_open_gl_address: fn(&str) -> *const std:
     gl::LinkProgram::load_with(get_open_gl_address);
     gl::ShaderSource::load_with(get_open_gl_address);
     gl::Uniform1f::load_with(get_open_gl_address);
+    gl::Uniform4f::load_with(get_open_gl_address);
     gl::UniformMatrix4fv::load_with(get_open_gl_address);
     gl::UseProgram::load_with(get_open_gl_address);
     gl::VertexAttribPointer::load_with(get_open_gl_address);
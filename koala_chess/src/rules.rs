@@ -0,0 +1,771 @@
+use crate::{
+    bitboard::{
+        bishop_attacks, coordinates, king_attacks, knight_attacks, pawn_attacks, rook_attacks,
+        square, square_bit, squares, Bitboard,
+    },
+    piece::{Piece, PieceColor, PieceKind},
+    zobrist::ZOBRIST_KEYS,
+};
+
+const PROMOTION_KINDS: [PieceKind; 4] = [
+    PieceKind::Queen,
+    PieceKind::Rook,
+    PieceKind::Bishop,
+    PieceKind::Knight,
+];
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct Move {
+    pub from_x: u8,
+    pub from_y: u8,
+    pub to_x: u8,
+    pub to_y: u8,
+    pub promotion: Option<PieceKind>,
+}
+
+impl Move {
+    pub fn new(from_x: u8, from_y: u8, to_x: u8, to_y: u8) -> Move {
+        Move {
+            from_x,
+            from_y,
+            to_x,
+            to_y,
+            promotion: None,
+        }
+    }
+
+    pub fn new_promotion(from_x: u8, from_y: u8, to_x: u8, to_y: u8, kind: PieceKind) -> Move {
+        Move {
+            from_x,
+            from_y,
+            to_x,
+            to_y,
+            promotion: Some(kind),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, Default)]
+pub struct CastlingRights {
+    pub white_king_side: bool,
+    pub white_queen_side: bool,
+    pub black_king_side: bool,
+    pub black_queen_side: bool,
+}
+
+impl CastlingRights {
+    pub fn new(pieces: &[Piece]) -> CastlingRights {
+        // A side may castle as long as neither its king nor the corresponding rook has moved
+        let unmoved = |color: PieceColor, kind: PieceKind, board_x: u8| {
+            let board_y = home_rank(color);
+
+            pieces.iter().any(|piece| {
+                piece.color == color
+                    && piece.kind == kind
+                    && piece.board_x == board_x
+                    && piece.board_y == board_y
+                    && !piece.has_moved
+            })
+        };
+
+        let white_king = unmoved(PieceColor::White, PieceKind::King, 4);
+        let black_king = unmoved(PieceColor::Black, PieceKind::King, 4);
+
+        CastlingRights {
+            white_king_side: white_king && unmoved(PieceColor::White, PieceKind::Rook, 7),
+            white_queen_side: white_king && unmoved(PieceColor::White, PieceKind::Rook, 0),
+            black_king_side: black_king && unmoved(PieceColor::Black, PieceKind::Rook, 7),
+            black_queen_side: black_king && unmoved(PieceColor::Black, PieceKind::Rook, 0),
+        }
+    }
+
+    fn king_side(&self, color: PieceColor) -> bool {
+        match color {
+            PieceColor::White => self.white_king_side,
+            PieceColor::Black => self.black_king_side,
+        }
+    }
+
+    fn queen_side(&self, color: PieceColor) -> bool {
+        match color {
+            PieceColor::White => self.white_queen_side,
+            PieceColor::Black => self.black_queen_side,
+        }
+    }
+
+    fn remove(&mut self, color: PieceColor) {
+        match color {
+            PieceColor::White => {
+                self.white_king_side = false;
+                self.white_queen_side = false;
+            }
+            PieceColor::Black => {
+                self.black_king_side = false;
+                self.black_queen_side = false;
+            }
+        }
+    }
+
+    fn remove_rook(&mut self, board_x: u8, board_y: u8) {
+        match (board_x, board_y) {
+            (7, 0) => self.white_king_side = false,
+            (0, 0) => self.white_queen_side = false,
+            (7, 7) => self.black_king_side = false,
+            (0, 7) => self.black_queen_side = false,
+            _ => (),
+        }
+    }
+}
+
+// Everything make_move can't derive from the position after the move, so that unmake_move can
+// restore the position before the move
+#[derive(Copy, Clone, Debug)]
+pub struct Undo {
+    captured: Option<(PieceColor, PieceKind)>,
+    castling_rights: CastlingRights,
+    en_passant_target: Option<(u8, u8)>,
+    hash: u64,
+    is_en_passant: bool,
+    is_castling: bool,
+}
+
+// Bitboard representation, which is used for move generation and search. Game keeps the list of
+// (renderable) pieces, which can be converted to and from a position
+// Reference: https://www.chessprogramming.org/Bitboards
+#[derive(Clone)]
+pub struct Position {
+    // pieces[color][kind]
+    pieces: [[Bitboard; 6]; 2],
+    // occupancy[color]
+    occupancy: [Bitboard; 2],
+    pub side_to_move: PieceColor,
+    pub castling_rights: CastlingRights,
+    pub en_passant_target: Option<(u8, u8)>,
+    // Zobrist hash, updated incrementally by make_move and unmake_move
+    hash: u64,
+}
+
+impl Position {
+    pub fn new(
+        pieces: &[Piece],
+        side_to_move: PieceColor,
+        en_passant_target: Option<(u8, u8)>,
+    ) -> Position {
+        let mut position = Position {
+            pieces: [[0; 6]; 2],
+            occupancy: [0; 2],
+            side_to_move,
+            castling_rights: CastlingRights::new(pieces),
+            en_passant_target,
+            hash: 0,
+        };
+
+        for piece in pieces {
+            position.toggle_piece(
+                piece.color,
+                piece.kind,
+                square(piece.board_x, piece.board_y),
+            );
+        }
+
+        position.hash = position.compute_hash();
+
+        position
+    }
+
+    // Kings and rooks are marked as moved if they have lost their castling rights
+    pub fn to_pieces(&self) -> Vec<Piece> {
+        let mut pieces = Vec::new();
+
+        for color in [PieceColor::White, PieceColor::Black] {
+            for kind in PieceKind::ALL {
+                for square in squares(self.pieces(color, kind)) {
+                    let (board_x, board_y) = coordinates(square);
+                    let mut piece = Piece::new(color, kind, board_x, board_y);
+
+                    let castling_rights = &self.castling_rights;
+                    let on_home_rank = board_y == home_rank(color);
+
+                    piece.has_moved = match kind {
+                        PieceKind::King => {
+                            !(on_home_rank
+                                && board_x == 4
+                                && (castling_rights.king_side(color)
+                                    || castling_rights.queen_side(color)))
+                        }
+                        PieceKind::Rook => {
+                            !(on_home_rank
+                                && ((board_x == 7 && castling_rights.king_side(color))
+                                    || (board_x == 0 && castling_rights.queen_side(color))))
+                        }
+                        _ => false,
+                    };
+
+                    pieces.push(piece);
+                }
+            }
+        }
+
+        pieces
+    }
+
+    pub fn pieces(&self, color: PieceColor, kind: PieceKind) -> Bitboard {
+        self.pieces[color.index()][kind.index()]
+    }
+
+    pub fn piece_at(&self, board_x: u8, board_y: u8) -> Option<(PieceColor, PieceKind)> {
+        let bit = square_bit(board_x, board_y);
+
+        let color = if self.occupancy[PieceColor::White.index()] & bit != 0 {
+            PieceColor::White
+        } else if self.occupancy[PieceColor::Black.index()] & bit != 0 {
+            PieceColor::Black
+        } else {
+            return None;
+        };
+
+        PieceKind::ALL
+            .into_iter()
+            .find(|kind| self.pieces(color, *kind) & bit != 0)
+            .map(|kind| (color, kind))
+    }
+
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = self.pseudo_legal_moves();
+        let mut position = self.clone();
+
+        // Only keep the moves which do not leave our own king in check. This covers pinned pieces
+        // as well as check evasions
+        moves.retain(|r#move| {
+            let undo = position.make_move(*r#move);
+            let is_legal = !position.is_in_check(self.side_to_move);
+            position.unmake_move(*r#move, undo);
+
+            is_legal
+        });
+
+        moves
+    }
+
+    // Positions with the same hash are considered to be the same position regarding repetitions
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn is_in_check(&self, color: PieceColor) -> bool {
+        match self.king_square(color) {
+            Some(square) => self.is_attacked(square, color.opposite()),
+            None => false,
+        }
+    }
+
+    pub fn is_square_attacked(&self, board_x: u8, board_y: u8, attacker: PieceColor) -> bool {
+        self.is_attacked(square(board_x, board_y), attacker)
+    }
+
+    pub fn is_castling(&self, r#move: Move) -> bool {
+        matches!(
+            self.piece_at(r#move.from_x, r#move.from_y),
+            Some((_, PieceKind::King))
+        ) && r#move.from_x.abs_diff(r#move.to_x) == 2
+    }
+
+    pub fn is_en_passant(&self, r#move: Move) -> bool {
+        matches!(
+            self.piece_at(r#move.from_x, r#move.from_y),
+            Some((_, PieceKind::Pawn))
+        ) && r#move.from_x != r#move.to_x
+            && self.piece_at(r#move.to_x, r#move.to_y).is_none()
+    }
+
+    pub fn make_move(&mut self, r#move: Move) -> Undo {
+        let keys = &*ZOBRIST_KEYS;
+        let is_en_passant = self.is_en_passant(r#move);
+        let is_castling = self.is_castling(r#move);
+        let undo = Undo {
+            captured: self.piece_at(r#move.to_x, r#move.to_y),
+            castling_rights: self.castling_rights,
+            en_passant_target: self.en_passant_target,
+            hash: self.hash,
+            is_en_passant,
+            is_castling,
+        };
+
+        let Some((color, kind)) = self.piece_at(r#move.from_x, r#move.from_y) else {
+            return undo;
+        };
+
+        // Remove the parts which are about to change from the hash
+        self.hash ^= keys.castling_rights(&self.castling_rights)
+            ^ self.en_passant_hash()
+            ^ keys.side_to_move(self.side_to_move);
+
+        if let Some((captured_color, captured_kind)) = undo.captured {
+            self.toggle_piece(
+                captured_color,
+                captured_kind,
+                square(r#move.to_x, r#move.to_y),
+            );
+        }
+
+        if is_en_passant {
+            // The captured pawn is located behind the target square
+            self.toggle_piece(
+                color.opposite(),
+                PieceKind::Pawn,
+                square(r#move.to_x, r#move.from_y),
+            );
+        }
+
+        if is_castling {
+            // Let the rook hop over the king
+            let (rook_from_x, rook_to_x) = castling_rook_files(r#move.to_x);
+            self.toggle_piece(color, PieceKind::Rook, square(rook_from_x, r#move.from_y));
+            self.toggle_piece(color, PieceKind::Rook, square(rook_to_x, r#move.from_y));
+        }
+
+        self.toggle_piece(color, kind, square(r#move.from_x, r#move.from_y));
+        self.toggle_piece(
+            color,
+            r#move.promotion.unwrap_or(kind),
+            square(r#move.to_x, r#move.to_y),
+        );
+
+        // Update castling rights
+        if kind == PieceKind::King {
+            self.castling_rights.remove(color);
+        }
+
+        self.castling_rights
+            .remove_rook(r#move.from_x, r#move.from_y);
+        self.castling_rights.remove_rook(r#move.to_x, r#move.to_y);
+
+        // Update en passant target
+        self.en_passant_target =
+            if kind == PieceKind::Pawn && r#move.from_y.abs_diff(r#move.to_y) == 2 {
+                Some((r#move.from_x, (r#move.from_y + r#move.to_y) / 2))
+            } else {
+                None
+            };
+
+        self.side_to_move = self.side_to_move.opposite();
+
+        self.hash ^= keys.castling_rights(&self.castling_rights)
+            ^ self.en_passant_hash()
+            ^ keys.side_to_move(self.side_to_move);
+
+        undo
+    }
+
+    // Takes back a move made by make_move (with the Undo it returned)
+    pub fn unmake_move(&mut self, r#move: Move, undo: Undo) {
+        let Some((color, kind)) = self.piece_at(r#move.to_x, r#move.to_y) else {
+            return;
+        };
+
+        // Promoted pieces turn back into pawns
+        self.toggle_piece(color, kind, square(r#move.to_x, r#move.to_y));
+        self.toggle_piece(
+            color,
+            r#move.promotion.map_or(kind, |_| PieceKind::Pawn),
+            square(r#move.from_x, r#move.from_y),
+        );
+
+        if let Some((captured_color, captured_kind)) = undo.captured {
+            self.toggle_piece(
+                captured_color,
+                captured_kind,
+                square(r#move.to_x, r#move.to_y),
+            );
+        }
+
+        if undo.is_en_passant {
+            self.toggle_piece(
+                color.opposite(),
+                PieceKind::Pawn,
+                square(r#move.to_x, r#move.from_y),
+            );
+        }
+
+        if undo.is_castling {
+            let (rook_from_x, rook_to_x) = castling_rook_files(r#move.to_x);
+            self.toggle_piece(color, PieceKind::Rook, square(rook_to_x, r#move.from_y));
+            self.toggle_piece(color, PieceKind::Rook, square(rook_from_x, r#move.from_y));
+        }
+
+        self.side_to_move = color;
+        self.castling_rights = undo.castling_rights;
+        self.en_passant_target = undo.en_passant_target;
+        self.hash = undo.hash;
+    }
+
+    fn pseudo_legal_moves(&self) -> Vec<Move> {
+        let color = self.side_to_move;
+        let own = self.occupancy[color.index()];
+        let occupied = self.occupied();
+        let mut moves = Vec::new();
+
+        self.add_pawn_moves(&mut moves);
+
+        for from in squares(self.pieces(color, PieceKind::Knight)) {
+            add_moves(from, knight_attacks(from) & !own, &mut moves);
+        }
+
+        for kind in [PieceKind::Bishop, PieceKind::Rook, PieceKind::Queen] {
+            for from in squares(self.pieces(color, kind)) {
+                let attacks = match kind {
+                    PieceKind::Bishop => bishop_attacks(from, occupied),
+                    PieceKind::Rook => rook_attacks(from, occupied),
+                    _ => bishop_attacks(from, occupied) | rook_attacks(from, occupied),
+                };
+
+                add_moves(from, attacks & !own, &mut moves);
+            }
+        }
+
+        if let Some(from) = self.king_square(color) {
+            add_moves(from, king_attacks(from) & !own, &mut moves);
+            self.add_castling_moves(from, &mut moves);
+        }
+
+        moves
+    }
+
+    fn add_pawn_moves(&self, moves: &mut Vec<Move>) {
+        let color = self.side_to_move;
+        let empty = !self.occupied();
+        let enemies = self.occupancy[color.opposite().index()];
+        let en_passant_target = self
+            .en_passant_target
+            .map_or(0, |(board_x, board_y)| square_bit(board_x, board_y));
+
+        let (direction, start_y) = match color {
+            PieceColor::White => (1, 1),
+            PieceColor::Black => (-1, 6),
+        };
+
+        for from in squares(self.pieces(color, PieceKind::Pawn)) {
+            let (board_x, board_y) = coordinates(from);
+            let to_y = (board_y as i8 + direction) as u8;
+
+            // Single and double push
+            if empty & square_bit(board_x, to_y) != 0 {
+                add_pawn_move(board_x, board_y, board_x, to_y, moves);
+
+                let double_push_y = (to_y as i8 + direction) as u8;
+
+                if board_y == start_y && empty & square_bit(board_x, double_push_y) != 0 {
+                    moves.push(Move::new(board_x, board_y, board_x, double_push_y));
+                }
+            }
+
+            // Captures
+            for to in squares(pawn_attacks(color, from) & (enemies | en_passant_target)) {
+                let (to_x, to_y) = coordinates(to);
+                add_pawn_move(board_x, board_y, to_x, to_y, moves);
+            }
+        }
+    }
+
+    fn add_castling_moves(&self, from: usize, moves: &mut Vec<Move>) {
+        let color = self.side_to_move;
+        let opponent = color.opposite();
+        let (board_x, board_y) = coordinates(from);
+
+        if board_x != 4 || board_y != home_rank(color) || self.is_in_check(color) {
+            return;
+        }
+
+        // The squares between king and rook have to be empty and the king must not pass through
+        // or land on an attacked square
+        if self.castling_rights.king_side(color)
+            && (5..=6).all(|x| self.piece_at(x, board_y).is_none())
+            && (5..=6).all(|x| !self.is_square_attacked(x, board_y, opponent))
+        {
+            moves.push(Move::new(board_x, board_y, 6, board_y));
+        }
+
+        if self.castling_rights.queen_side(color)
+            && (1..=3).all(|x| self.piece_at(x, board_y).is_none())
+            && (2..=3).all(|x| !self.is_square_attacked(x, board_y, opponent))
+        {
+            moves.push(Move::new(board_x, board_y, 2, board_y));
+        }
+    }
+
+    fn is_attacked(&self, square: usize, attacker: PieceColor) -> bool {
+        let occupied = self.occupied();
+        let queens = self.pieces(attacker, PieceKind::Queen);
+
+        // Pawns attack diagonally forward, so we have to look diagonally backward
+        pawn_attacks(attacker.opposite(), square) & self.pieces(attacker, PieceKind::Pawn) != 0
+            || knight_attacks(square) & self.pieces(attacker, PieceKind::Knight) != 0
+            || king_attacks(square) & self.pieces(attacker, PieceKind::King) != 0
+            || bishop_attacks(square, occupied)
+                & (self.pieces(attacker, PieceKind::Bishop) | queens)
+                != 0
+            || rook_attacks(square, occupied) & (self.pieces(attacker, PieceKind::Rook) | queens)
+                != 0
+    }
+
+    fn occupied(&self) -> Bitboard {
+        self.occupancy[0] | self.occupancy[1]
+    }
+
+    // Adds or removes a piece and updates the hash accordingly
+    fn toggle_piece(&mut self, color: PieceColor, kind: PieceKind, square: usize) {
+        let bit = 1 << square;
+        let (board_x, board_y) = coordinates(square);
+
+        self.pieces[color.index()][kind.index()] ^= bit;
+        self.occupancy[color.index()] ^= bit;
+        self.hash ^= ZOBRIST_KEYS.piece(color, kind, board_x, board_y);
+    }
+
+    fn compute_hash(&self) -> u64 {
+        let keys = &*ZOBRIST_KEYS;
+        let mut hash = keys.castling_rights(&self.castling_rights)
+            ^ self.en_passant_hash()
+            ^ keys.side_to_move(self.side_to_move);
+
+        for color in [PieceColor::White, PieceColor::Black] {
+            for kind in PieceKind::ALL {
+                for square in squares(self.pieces(color, kind)) {
+                    let (board_x, board_y) = coordinates(square);
+                    hash ^= keys.piece(color, kind, board_x, board_y);
+                }
+            }
+        }
+
+        hash
+    }
+
+    // The en passant target only matters if a pawn of the side to move could capture en passant
+    fn en_passant_hash(&self) -> u64 {
+        let Some((target_x, target_y)) = self.en_passant_target else {
+            return 0;
+        };
+
+        // The pawns which could capture are the ones an opposing pawn on the target square would
+        // attack
+        let capturing_pawns =
+            pawn_attacks(self.side_to_move.opposite(), square(target_x, target_y))
+                & self.pieces(self.side_to_move, PieceKind::Pawn);
+
+        if capturing_pawns != 0 {
+            ZOBRIST_KEYS.en_passant_file(target_x)
+        } else {
+            0
+        }
+    }
+
+    fn king_square(&self, color: PieceColor) -> Option<usize> {
+        squares(self.pieces(color, PieceKind::King)).next()
+    }
+}
+
+fn add_moves(from: usize, targets: Bitboard, moves: &mut Vec<Move>) {
+    let (from_x, from_y) = coordinates(from);
+
+    for to in squares(targets) {
+        let (to_x, to_y) = coordinates(to);
+        moves.push(Move::new(from_x, from_y, to_x, to_y));
+    }
+}
+
+fn add_pawn_move(from_x: u8, from_y: u8, to_x: u8, to_y: u8, moves: &mut Vec<Move>) {
+    if to_y == 0 || to_y == 7 {
+        for kind in PROMOTION_KINDS {
+            moves.push(Move::new_promotion(from_x, from_y, to_x, to_y, kind));
+        }
+    } else {
+        moves.push(Move::new(from_x, from_y, to_x, to_y));
+    }
+}
+
+pub fn home_rank(color: PieceColor) -> u8 {
+    match color {
+        PieceColor::White => 0,
+        PieceColor::Black => 7,
+    }
+}
+
+// Returns the files the rook moves from and to when the king castles to the given file
+pub fn castling_rook_files(king_to_x: u8) -> (u8, u8) {
+    if king_to_x == 6 {
+        (7, 5)
+    } else {
+        (0, 3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fen, uci};
+
+    fn position(fen: &str) -> Position {
+        let fen = fen::parse(fen).unwrap();
+
+        Position::new(&fen.pieces, fen.side_to_move, fen.en_passant_target)
+    }
+
+    // Legal moves in long algebraic notation (sorted), optionally only of the piece on a square
+    fn legal_moves(position: &Position, from: Option<&str>) -> Vec<String> {
+        let from = from.map(|square| fen::parse_square(square).unwrap());
+        let mut moves: Vec<String> = position
+            .legal_moves()
+            .into_iter()
+            .filter(|r#move| from.is_none_or(|from| (r#move.from_x, r#move.from_y) == from))
+            .map(uci::format_move)
+            .collect();
+
+        moves.sort();
+        moves
+    }
+
+    #[test]
+    fn pinned_piece_cannot_leave_the_pin_line() {
+        // The bishop is pinned against the king
+        let position = position("4k3/4r3/8/8/8/8/4B3/4K3 w - - 0 1");
+        assert!(legal_moves(&position, Some("e2")).is_empty());
+
+        // The rook may move along the pin line (including the capture of the pinning rook)
+        let position = self::position("4k3/4r3/8/8/8/8/4R3/4K3 w - - 0 1");
+        assert_eq!(
+            legal_moves(&position, Some("e2")),
+            ["e2e3", "e2e4", "e2e5", "e2e6", "e2e7"]
+        );
+    }
+
+    #[test]
+    fn check_is_evaded_by_moving_capturing_or_blocking() {
+        let position = position("4k3/8/8/8/8/8/1B6/r3K3 w - - 0 1");
+
+        assert!(position.is_in_check(PieceColor::White));
+        assert_eq!(
+            legal_moves(&position, None),
+            ["b2a1", "b2c1", "e1d2", "e1e2", "e1f2"]
+        );
+    }
+
+    #[test]
+    fn double_check_is_only_evaded_by_the_king() {
+        // Rook a1 and knight f3 give check, the bishop could capture the rook or block it. The
+        // knight covers d2
+        let position = position("4k3/8/8/8/8/5n2/1B6/r3K3 w - - 0 1");
+
+        assert_eq!(legal_moves(&position, None), ["e1e2", "e1f2"]);
+    }
+
+    #[test]
+    fn king_cannot_move_into_check() {
+        // The squares of the d- and f-file are attacked by the rooks
+        let position = position("3rkr2/8/8/8/8/8/8/4K3 w - - 0 1");
+
+        assert_eq!(legal_moves(&position, None), ["e1e2"]);
+    }
+
+    #[test]
+    fn castling_through_or_out_of_check_is_illegal() {
+        // The rook on f8 attacks f1, which the king passes when castling king side
+        let position = position("4kr2/8/8/8/8/8/8/R3K2R w KQ - 0 1");
+        let moves = legal_moves(&position, Some("e1"));
+        assert!(!moves.contains(&"e1g1".to_string()));
+        assert!(moves.contains(&"e1c1".to_string()));
+
+        // Only the rook passes b1 when castling queen side
+        let position = self::position("1r2k3/8/8/8/8/8/8/R3K2R w KQ - 0 1");
+        let moves = legal_moves(&position, Some("e1"));
+        assert!(moves.contains(&"e1c1".to_string()));
+        assert!(moves.contains(&"e1g1".to_string()));
+
+        // The king may not castle out of check
+        let position = self::position("4r1k1/8/8/8/8/8/8/R3K2R w KQ - 0 1");
+        let moves = legal_moves(&position, Some("e1"));
+        assert!(!moves.contains(&"e1c1".to_string()));
+        assert!(!moves.contains(&"e1g1".to_string()));
+    }
+
+    #[test]
+    fn castling_moves_the_rook_and_is_undone() {
+        let mut position = position("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        let hash = position.hash();
+        let r#move = uci::parse_move(&position, "e1g1").unwrap();
+
+        assert!(position.is_castling(r#move));
+
+        let undo = position.make_move(r#move);
+        assert_eq!(
+            position.piece_at(6, 0),
+            Some((PieceColor::White, PieceKind::King))
+        );
+        assert_eq!(
+            position.piece_at(5, 0),
+            Some((PieceColor::White, PieceKind::Rook))
+        );
+        assert_eq!(position.piece_at(7, 0), None);
+        assert!(!position.castling_rights.king_side(PieceColor::White));
+        assert!(!position.castling_rights.queen_side(PieceColor::White));
+
+        position.unmake_move(r#move, undo);
+        assert_eq!(position.hash(), hash);
+        assert_eq!(
+            position.piece_at(7, 0),
+            Some((PieceColor::White, PieceKind::Rook))
+        );
+    }
+
+    #[test]
+    fn en_passant_removes_the_captured_pawn() {
+        let mut position = position("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 1");
+        let r#move = uci::parse_move(&position, "d5e6").unwrap();
+
+        assert!(position.is_en_passant(r#move));
+
+        position.make_move(r#move);
+        assert_eq!(
+            position.piece_at(4, 5),
+            Some((PieceColor::White, PieceKind::Pawn))
+        );
+        assert_eq!(position.piece_at(4, 4), None);
+        assert_eq!(position.piece_at(3, 4), None);
+    }
+
+    #[test]
+    fn en_passant_exposing_the_king_is_illegal() {
+        // Both pawns leave the fifth rank, which the rook on h5 attacks along
+        let position = position("4k3/8/8/K2Pp2r/8/8/8/8 w - e6 0 1");
+
+        assert_eq!(legal_moves(&position, Some("d5")), ["d5d6"]);
+    }
+
+    #[test]
+    fn pawns_promote_to_every_piece() {
+        let mut position = position("1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1");
+
+        assert_eq!(
+            legal_moves(&position, Some("a7")),
+            ["a7a8b", "a7a8n", "a7a8q", "a7a8r", "a7b8b", "a7b8n", "a7b8q", "a7b8r"]
+        );
+
+        // Underpromotion with a capture
+        let r#move = uci::parse_move(&position, "a7b8n").unwrap();
+        let undo = position.make_move(r#move);
+        assert_eq!(
+            position.piece_at(1, 7),
+            Some((PieceColor::White, PieceKind::Knight))
+        );
+        assert_eq!(position.piece_at(0, 6), None);
+
+        position.unmake_move(r#move, undo);
+        assert_eq!(
+            position.piece_at(0, 6),
+            Some((PieceColor::White, PieceKind::Pawn))
+        );
+        assert_eq!(
+            position.piece_at(1, 7),
+            Some((PieceColor::Black, PieceKind::Knight))
+        );
+    }
+}
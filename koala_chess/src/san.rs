@@ -0,0 +1,360 @@
+use crate::{
+    fen::{format_square, parse_square},
+    piece::PieceKind,
+    rules::{Move, Position},
+};
+use std::error::Error;
+
+// Standard Algebraic Notation
+// Reference: https://www.chessprogramming.org/Algebraic_Chess_Notation#Standard_Algebraic_Notation_.28SAN.29
+pub fn format(position: &Position, r#move: Move) -> String {
+    let Some((_, kind)) = position.piece_at(r#move.from_x, r#move.from_y) else {
+        return String::new();
+    };
+
+    let mut san = String::new();
+
+    if position.is_castling(r#move) {
+        san.push_str(if r#move.to_x == 6 { "O-O" } else { "O-O-O" });
+    } else {
+        let is_capture =
+            position.is_en_passant(r#move) || position.piece_at(r#move.to_x, r#move.to_y).is_some();
+
+        if kind == PieceKind::Pawn {
+            if is_capture {
+                san.push((b'a' + r#move.from_x) as char);
+            }
+        } else {
+            san.push(piece_letter(kind));
+
+            // Disambiguate between pieces of the same kind which can move to the same square
+            let others: Vec<Move> = position
+                .legal_moves()
+                .into_iter()
+                .filter(|other| {
+                    other.to_x == r#move.to_x
+                        && other.to_y == r#move.to_y
+                        && (other.from_x, other.from_y) != (r#move.from_x, r#move.from_y)
+                        && position.piece_at(other.from_x, other.from_y)
+                            == position.piece_at(r#move.from_x, r#move.from_y)
+                })
+                .collect();
+
+            if !others.is_empty() {
+                if others.iter().all(|other| other.from_x != r#move.from_x) {
+                    san.push((b'a' + r#move.from_x) as char);
+                } else if others.iter().all(|other| other.from_y != r#move.from_y) {
+                    san.push((b'1' + r#move.from_y) as char);
+                } else {
+                    san.push_str(&format_square(r#move.from_x, r#move.from_y));
+                }
+            }
+        }
+
+        if is_capture {
+            san.push('x');
+        }
+
+        san.push_str(&format_square(r#move.to_x, r#move.to_y));
+
+        if let Some(promotion) = r#move.promotion {
+            san.push('=');
+            san.push(piece_letter(promotion));
+        }
+    }
+
+    let mut position = position.clone();
+    position.make_move(r#move);
+
+    if position.is_in_check(position.side_to_move) {
+        san.push(if position.legal_moves().is_empty() {
+            '#'
+        } else {
+            '+'
+        });
+    }
+
+    san
+}
+
+// Parses a move in SAN. We are lenient regarding check/checkmate indicators, move suffix
+// annotations, superfluous disambiguations, castling with zeros and promotions without '='
+pub fn parse(position: &Position, san: &str) -> Result<Move, Box<dyn Error>> {
+    let text = san
+        .trim()
+        .trim_end_matches(['+', '#', '!', '?'])
+        .trim_end_matches("e.p.")
+        .trim_end();
+
+    let legal_moves = position.legal_moves();
+
+    // Castling
+    if let Some(to_x) = match text {
+        "O-O" | "0-0" => Some(6),
+        "O-O-O" | "0-0-0" => Some(2),
+        _ => None,
+    } {
+        return legal_moves
+            .into_iter()
+            .find(|r#move| position.is_castling(*r#move) && r#move.to_x == to_x)
+            .ok_or_else(|| format!("Illegal move '{san}'!").into());
+    }
+
+    let mut chars: Vec<char> = text.chars().collect();
+
+    // Piece
+    let kind = match chars.first().and_then(|c| parse_piece_letter(*c)) {
+        Some(kind) => {
+            chars.remove(0);
+            kind
+        }
+        None => PieceKind::Pawn,
+    };
+
+    // Promotion (e.g. "e8=Q", "e8Q" or "e8=q")
+    let mut promotion = None;
+
+    if kind == PieceKind::Pawn && chars.len() >= 3 {
+        let last = chars[chars.len() - 1];
+        let has_equal_sign = chars[chars.len() - 2] == '=';
+
+        if let Some(promotion_kind) = parse_piece_letter(last.to_ascii_uppercase())
+            .filter(|_| has_equal_sign || last.is_ascii_uppercase())
+        {
+            promotion = Some(promotion_kind);
+            chars.truncate(chars.len() - if has_equal_sign { 2 } else { 1 });
+        }
+    }
+
+    // Target square
+    if chars.len() < 2 {
+        return Err(format!("Invalid SAN: missing target square! ({san})").into());
+    }
+
+    let target: String = chars.split_off(chars.len() - 2).into_iter().collect();
+    let (to_x, to_y) = parse_square(&target)
+        .ok_or_else(|| format!("Invalid SAN: invalid target square '{target}'! ({san})"))?;
+
+    // Capture indicator and disambiguation
+    if matches!(chars.last(), Some('x' | ':')) {
+        chars.pop();
+    }
+
+    let mut from_x = None;
+    let mut from_y = None;
+
+    for c in chars {
+        match c {
+            'a'..='h' if from_x.is_none() => from_x = Some(c as u8 - b'a'),
+            '1'..='8' if from_y.is_none() => from_y = Some(c as u8 - b'1'),
+            _ => return Err(format!("Invalid SAN: unexpected character '{c}'! ({san})").into()),
+        }
+    }
+
+    let candidates: Vec<Move> = legal_moves
+        .into_iter()
+        .filter(|r#move| {
+            r#move.to_x == to_x
+                && r#move.to_y == to_y
+                && from_x.is_none_or(|from_x| r#move.from_x == from_x)
+                && from_y.is_none_or(|from_y| r#move.from_y == from_y)
+                && matches!(position.piece_at(r#move.from_x, r#move.from_y), Some((_, piece_kind)) if piece_kind == kind)
+                && !position.is_castling(*r#move)
+        })
+        .collect();
+
+    if candidates.iter().any(|r#move| r#move.promotion.is_some()) && promotion.is_none() {
+        return Err(format!("Invalid SAN: missing promotion piece! ({san})").into());
+    }
+
+    let candidates: Vec<Move> = candidates
+        .into_iter()
+        .filter(|r#move| r#move.promotion == promotion)
+        .collect();
+
+    match candidates.as_slice() {
+        [r#move] => Ok(*r#move),
+        [] => Err(format!("Illegal move '{san}'!").into()),
+        _ => Err(format!(
+            "Ambiguous move '{san}'! ({})",
+            candidates
+                .iter()
+                .map(|r#move| format(position, *r#move))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+        .into()),
+    }
+}
+
+pub fn piece_letter(kind: PieceKind) -> char {
+    match kind {
+        PieceKind::Pawn => 'P',
+        PieceKind::Knight => 'N',
+        PieceKind::Bishop => 'B',
+        PieceKind::Rook => 'R',
+        PieceKind::Queen => 'Q',
+        PieceKind::King => 'K',
+    }
+}
+
+pub fn parse_piece_letter(c: char) -> Option<PieceKind> {
+    match c {
+        'N' => Some(PieceKind::Knight),
+        'B' => Some(PieceKind::Bishop),
+        'R' => Some(PieceKind::Rook),
+        'Q' => Some(PieceKind::Queen),
+        'K' => Some(PieceKind::King),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fen, uci};
+
+    fn position(fen: &str) -> Position {
+        let fen = fen::parse(fen).unwrap();
+
+        Position::new(&fen.pieces, fen.side_to_move, fen.en_passant_target)
+    }
+
+    // Formats a move given in long algebraic notation
+    fn format_uci(fen: &str, text: &str) -> String {
+        let position = position(fen);
+        let r#move = uci::parse_move(&position, text).unwrap();
+
+        format(&position, r#move)
+    }
+
+    // Parses a move and returns it in long algebraic notation
+    fn parse_uci(fen: &str, san: &str) -> Result<String, Box<dyn Error>> {
+        parse(&position(fen), san).map(uci::format_move)
+    }
+
+    #[test]
+    fn format_moves() {
+        let fen = "r3k3/8/8/3p4/4P3/8/8/R3K2R w KQq - 0 1";
+
+        assert_eq!(format_uci(fen, "e4e5"), "e5");
+        assert_eq!(format_uci(fen, "e4d5"), "exd5");
+        assert_eq!(format_uci(fen, "a1a8"), "Rxa8+");
+        assert_eq!(format_uci(fen, "e1g1"), "O-O");
+        assert_eq!(format_uci(fen, "e1c1"), "O-O-O");
+        assert_eq!(
+            format_uci("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 1", "d5e6"),
+            "dxe6"
+        );
+    }
+
+    #[test]
+    fn format_disambiguation() {
+        // Knights on b1 and f1: the file differs
+        let fen = "4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1";
+        assert_eq!(format_uci(fen, "b1d2"), "Nbd2");
+        assert_eq!(format_uci(fen, "f1d2"), "Nfd2");
+
+        // Rooks on a1 and a5: only the rank differs
+        let fen = "4k3/8/8/R7/8/8/8/R3K3 w - - 0 1";
+        assert_eq!(format_uci(fen, "a1a3"), "R1a3");
+        assert_eq!(format_uci(fen, "a5a3"), "R5a3");
+
+        // Queens on a1, c1 and a3: neither the file nor the rank is unique
+        let fen = "8/7k/8/8/8/Q7/8/Q1Q4K w - - 0 1";
+        assert_eq!(format_uci(fen, "a1b2"), "Qa1b2");
+        assert_eq!(format_uci(fen, "c1b2"), "Qcb2");
+        assert_eq!(format_uci(fen, "a3b2"), "Q3b2");
+
+        // A pinned knight does not need to be distinguished
+        let fen = "4k3/8/8/8/8/8/8/rN2KN2 w - - 0 1";
+        assert_eq!(format_uci(fen, "f1d2"), "Nd2");
+    }
+
+    #[test]
+    fn format_promotion() {
+        let fen = "r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1";
+
+        assert_eq!(format_uci(fen, "b7b8q"), "b8=Q+");
+        assert_eq!(format_uci(fen, "b7b8n"), "b8=N");
+        assert_eq!(format_uci(fen, "b7a8r"), "bxa8=R+");
+        assert_eq!(format_uci(fen, "b7a8b"), "bxa8=B");
+    }
+
+    #[test]
+    fn format_check_and_checkmate() {
+        let fen = "6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1";
+
+        assert_eq!(format_uci(fen, "a1a8"), "Ra8#");
+        assert_eq!(format_uci(fen, "a1d1"), "Rd1");
+
+        // Discovered check
+        let fen = "4k3/8/8/8/8/8/4B3/4R1K1 w - - 0 1";
+        assert_eq!(format_uci(fen, "e2d3"), "Bd3+");
+    }
+
+    #[test]
+    fn parse_moves() {
+        let fen = "r3k3/1P6/8/3p4/4P3/8/8/R3K2R w KQq - 0 1";
+
+        assert_eq!(parse_uci(fen, "e5").unwrap(), "e4e5");
+        assert_eq!(parse_uci(fen, "exd5").unwrap(), "e4d5");
+        assert_eq!(parse_uci(fen, "Rxa8").unwrap(), "a1a8");
+        assert_eq!(parse_uci(fen, "O-O").unwrap(), "e1g1");
+        assert_eq!(parse_uci(fen, "O-O-O").unwrap(), "e1c1");
+        assert_eq!(parse_uci(fen, "b8=Q+").unwrap(), "b7b8q");
+        assert_eq!(parse_uci(fen, "bxa8=N").unwrap(), "b7a8n");
+    }
+
+    #[test]
+    fn parse_disambiguation() {
+        let fen = "4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1";
+        assert_eq!(parse_uci(fen, "Nbd2").unwrap(), "b1d2");
+        assert_eq!(parse_uci(fen, "Nfd2").unwrap(), "f1d2");
+        assert!(parse_uci(fen, "Nd2").is_err());
+
+        let fen = "4k3/8/8/R7/8/8/8/R3K3 w - - 0 1";
+        assert_eq!(parse_uci(fen, "R1a3").unwrap(), "a1a3");
+        assert_eq!(parse_uci(fen, "R5a3").unwrap(), "a5a3");
+        assert!(parse_uci(fen, "Ra3").is_err());
+
+        let fen = "8/7k/8/8/8/Q7/8/Q1Q4K w - - 0 1";
+        assert_eq!(parse_uci(fen, "Qa1b2").unwrap(), "a1b2");
+        assert!(parse_uci(fen, "Qab2").is_err());
+        assert!(parse_uci(fen, "Q1b2").is_err());
+    }
+
+    #[test]
+    fn parse_is_lenient() {
+        let fen = "r3k3/1P6/8/8/8/8/8/R3K2R w KQq - 0 1";
+
+        // Check/checkmate indicators and move suffix annotations
+        assert_eq!(parse_uci(fen, "Rxa8+").unwrap(), "a1a8");
+        assert_eq!(parse_uci(fen, "Rxa8#!?").unwrap(), "a1a8");
+        assert_eq!(parse_uci(fen, "Rxa8").unwrap(), "a1a8");
+
+        // Castling with zeros, superfluous disambiguation, promotion without '='
+        assert_eq!(parse_uci(fen, "0-0").unwrap(), "e1g1");
+        assert_eq!(parse_uci(fen, "Ra1xa8").unwrap(), "a1a8");
+        assert_eq!(parse_uci(fen, "b8Q").unwrap(), "b7b8q");
+        assert_eq!(parse_uci(fen, "b8=q").unwrap(), "b7b8q");
+
+        // En passant suffix
+        let fen = "4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 1";
+        assert_eq!(parse_uci(fen, "dxe6 e.p.").unwrap(), "d5e6");
+    }
+
+    #[test]
+    fn parse_errors() {
+        let fen = "r3k3/1P6/8/8/8/8/8/R3K2R w KQq - 0 1";
+
+        assert!(parse_uci(fen, "").is_err());
+        assert!(parse_uci(fen, "N").is_err());
+        assert!(parse_uci(fen, "Ri9").is_err());
+        assert!(parse_uci(fen, "Rxa9").is_err());
+        assert!(parse_uci(fen, "b8").is_err());
+        assert!(parse_uci(fen, "Nf3").is_err());
+        assert!(parse_uci(fen, "Kf2?x").is_err());
+        assert!(parse_uci("4k3/8/8/8/8/8/8/4K3 w - - 0 1", "O-O").is_err());
+    }
+}
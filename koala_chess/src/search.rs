@@ -0,0 +1,568 @@
+use crate::{
+    evaluation::Evaluation,
+    piece::PieceKind,
+    rules::{Move, Position, Undo},
+    transposition_table::{Bound, TranspositionTable},
+};
+use logger::*;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+// Scores above MATE_SCORE - MAX_PLY are mate scores
+pub const MATE_SCORE: i32 = 100_000;
+pub const MAX_PLY: usize = 128;
+const INFINITY: i32 = 1_000_000;
+// Time which is kept in reserve to account for the communication with a GUI
+pub const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+// Number of moves the remaining time is divided by (if the number of moves until the next time
+// control is unknown)
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+// Number of nodes between two checks of the stop flag and the time limit
+const STOP_CHECK_INTERVAL: u64 = 2048;
+
+// Move ordering priorities (higher scores are searched first)
+const BEST_MOVE_PRIORITY: i32 = 1_000_000_000;
+const CAPTURE_PRIORITY: i32 = 100_000_000;
+const PROMOTION_PRIORITY: i32 = 90_000_000;
+const KILLER_PRIORITY: i32 = 80_000_000;
+
+#[derive(Copy, Clone, Debug)]
+pub struct SearchResult {
+    pub best_move: Option<Move>,
+    // Score from the point of view of the side to move (in centipawns)
+    pub score: i32,
+    pub depth: u32,
+    pub nodes: u64,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct SearchLimits {
+    pub max_depth: u32,
+    pub time_limit: Option<Duration>,
+}
+
+pub struct Search<'a> {
+    evaluation: Evaluation,
+    transposition_table: &'a mut TranspositionTable,
+    // Set from the outside (e.g. another thread) to stop the search as soon as possible
+    stop: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+    stopped: bool,
+    // Quiet moves which caused a beta cutoff (per ply)
+    killers: [[Option<Move>; 2]; MAX_PLY],
+    // Quiet moves which caused a beta cutoff (per from and to square)
+    history: [[i32; 64]; 64],
+    // Hashes of the positions from the start of the game up to the current node, used to detect
+    // repetitions
+    position_hashes: Vec<u64>,
+    nodes: u64,
+}
+
+impl<'a> Search<'a> {
+    pub fn new(
+        evaluation: Evaluation,
+        transposition_table: &'a mut TranspositionTable,
+        stop: Arc<AtomicBool>,
+    ) -> Search<'a> {
+        Search {
+            evaluation,
+            transposition_table,
+            stop,
+            deadline: None,
+            stopped: false,
+            killers: [[None; 2]; MAX_PLY],
+            history: [[0; 64]; 64],
+            position_hashes: Vec::new(),
+            nodes: 0,
+        }
+    }
+
+    // Iterative deepening: every iteration searches one ply deeper and starts with the best move of
+    // the previous iteration. An iteration which is interrupted by the limits is discarded
+    // position_hashes contains the hashes of all positions since the game started (including the
+    // current one)
+    pub fn search(
+        &mut self,
+        position: &Position,
+        position_hashes: &[u64],
+        limits: SearchLimits,
+    ) -> SearchResult {
+        let mut position = position.clone();
+
+        self.transposition_table.new_search();
+        self.position_hashes = position_hashes.to_vec();
+
+        if self.position_hashes.last() != Some(&position.hash()) {
+            self.position_hashes.push(position.hash());
+        }
+
+        self.killers = [[None; 2]; MAX_PLY];
+        self.history = [[0; 64]; 64];
+        self.nodes = 0;
+        self.deadline = limits
+            .time_limit
+            .map(|time_limit| Instant::now() + time_limit);
+        self.stopped = false;
+
+        let mut result = SearchResult {
+            best_move: None,
+            score: 0,
+            depth: 0,
+            nodes: 0,
+        };
+
+        for depth in 1..=limits.max_depth.max(1) {
+            let (best_move, score) = self.search_root(&mut position, depth, result.best_move);
+
+            // Fall back to the (partial) first iteration if there is no result yet
+            if self.stopped && result.best_move.is_some() {
+                break;
+            }
+
+            result = SearchResult {
+                best_move,
+                score,
+                depth,
+                nodes: self.nodes,
+            };
+
+            debug!(
+                "Search: depth: {} / score: {} / nodes: {} / best move: {:?}",
+                depth, score, self.nodes, best_move
+            );
+
+            // There is no need to search deeper if a forced mate has been found
+            if self.stopped || score.abs() >= MATE_SCORE - MAX_PLY as i32 {
+                break;
+            }
+        }
+
+        result
+    }
+
+    fn search_root(
+        &mut self,
+        position: &mut Position,
+        depth: u32,
+        previous_best_move: Option<Move>,
+    ) -> (Option<Move>, i32) {
+        let mut moves = position.legal_moves();
+        self.order_moves(position, &mut moves, 0, previous_best_move);
+
+        let mut alpha = -INFINITY;
+        let mut best_move = moves.first().copied();
+
+        for r#move in moves {
+            let undo = self.make_move(position, r#move);
+            let score = -self.alpha_beta(position, depth - 1, 1, -INFINITY, -alpha);
+            self.unmake_move(position, r#move, undo);
+
+            if self.stopped {
+                break;
+            }
+
+            if score > alpha {
+                alpha = score;
+                best_move = Some(r#move);
+            }
+        }
+
+        match best_move {
+            // The first move might not have been searched completely
+            Some(_) if alpha == -INFINITY => (best_move, 0),
+            Some(_) => (best_move, alpha),
+            // Checkmate or stalemate
+            None if position.is_in_check(position.side_to_move) => (None, -MATE_SCORE),
+            None => (None, 0),
+        }
+    }
+
+    // Negamax with alpha-beta pruning
+    fn alpha_beta(
+        &mut self,
+        position: &mut Position,
+        depth: u32,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        // A repetition is scored as a draw, because the side which is better off would avoid it
+        if self.is_repetition() {
+            return 0;
+        }
+
+        if depth == 0 || ply >= MAX_PLY {
+            return self.quiescence(position, ply, alpha, beta);
+        }
+
+        self.nodes += 1;
+
+        if self.should_stop() {
+            return 0;
+        }
+
+        let hash = position.hash();
+        let entry = self.transposition_table.probe(hash);
+
+        // The stored result can be used if it has been searched at least as deep
+        if let Some(entry) = entry.filter(|entry| entry.depth >= depth) {
+            let score = entry.score(ply);
+
+            match entry.bound {
+                Bound::Exact => return score.clamp(alpha, beta),
+                Bound::Lower if score >= beta => return beta,
+                Bound::Upper if score <= alpha => return alpha,
+                _ => (),
+            }
+        }
+
+        let mut moves = position.legal_moves();
+
+        if moves.is_empty() {
+            // Prefer faster mates
+            return if position.is_in_check(position.side_to_move) {
+                -MATE_SCORE + ply as i32
+            } else {
+                0
+            };
+        }
+
+        // The best move of an earlier search is tried first
+        let entry_move = entry.and_then(|entry| entry.best_move);
+        self.order_moves(position, &mut moves, ply, entry_move);
+
+        let mut best_move = None;
+
+        for r#move in moves {
+            let is_quiet = !is_capture(position, r#move) && r#move.promotion.is_none();
+
+            let undo = self.make_move(position, r#move);
+            let score = -self.alpha_beta(position, depth - 1, ply + 1, -beta, -alpha);
+            self.unmake_move(position, r#move, undo);
+
+            if self.stopped {
+                return 0;
+            }
+
+            if score >= beta {
+                if is_quiet {
+                    self.store_killer(r#move, ply);
+                    self.history[from_index(r#move)][to_index(r#move)] += (depth * depth) as i32;
+                }
+
+                self.transposition_table
+                    .store(hash, Some(r#move), beta, depth, Bound::Lower, ply);
+
+                return beta;
+            }
+
+            if score > alpha {
+                alpha = score;
+                best_move = Some(r#move);
+            }
+        }
+
+        // Without a best move no score has been above alpha, so alpha is only an upper bound
+        let bound = if best_move.is_some() {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+
+        self.transposition_table
+            .store(hash, best_move, alpha, depth, bound, ply);
+
+        alpha
+    }
+
+    // Only captures and promotions are searched to avoid misjudging positions in the middle of an
+    // exchange (horizon effect)
+    fn quiescence(
+        &mut self,
+        position: &mut Position,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        self.nodes += 1;
+
+        if self.should_stop() {
+            return 0;
+        }
+
+        let in_check = position.is_in_check(position.side_to_move);
+        let mut moves = position.legal_moves();
+
+        if moves.is_empty() {
+            return if in_check {
+                -MATE_SCORE + ply as i32
+            } else {
+                0
+            };
+        }
+
+        // Standing pat is not allowed while in check, so every evasion is searched instead
+        if !in_check {
+            let stand_pat = self.evaluation.evaluate(position);
+
+            if stand_pat >= beta || ply >= MAX_PLY {
+                return stand_pat;
+            }
+
+            alpha = alpha.max(stand_pat);
+
+            moves.retain(|r#move| is_capture(position, *r#move) || r#move.promotion.is_some());
+        } else if ply >= MAX_PLY {
+            return self.evaluation.evaluate(position);
+        }
+
+        self.order_moves(position, &mut moves, ply, None);
+
+        for r#move in moves {
+            let undo = position.make_move(r#move);
+            let score = -self.quiescence(position, ply + 1, -beta, -alpha);
+            position.unmake_move(r#move, undo);
+
+            if self.stopped {
+                return 0;
+            }
+
+            if score >= beta {
+                return beta;
+            }
+
+            alpha = alpha.max(score);
+        }
+
+        alpha
+    }
+
+    // Makes the move and keeps track of the position for repetition detection
+    fn make_move(&mut self, position: &mut Position, r#move: Move) -> Undo {
+        let undo = position.make_move(r#move);
+        self.position_hashes.push(position.hash());
+
+        undo
+    }
+
+    fn unmake_move(&mut self, position: &mut Position, r#move: Move, undo: Undo) {
+        self.position_hashes.pop();
+        position.unmake_move(r#move, undo);
+    }
+
+    // Whether the current position occurred before. Only every second position can be the same,
+    // because the side to move has to be the same as well
+    fn is_repetition(&self) -> bool {
+        let Some((hash, previous_hashes)) = self.position_hashes.split_last() else {
+            return false;
+        };
+
+        previous_hashes
+            .iter()
+            .rev()
+            .skip(1)
+            .step_by(2)
+            .any(|previous_hash| previous_hash == hash)
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if !self.stopped && self.nodes.is_multiple_of(STOP_CHECK_INTERVAL) {
+            self.stopped = self.stop.load(Ordering::Relaxed)
+                || self
+                    .deadline
+                    .is_some_and(|deadline| Instant::now() >= deadline);
+        }
+
+        self.stopped
+    }
+
+    fn order_moves(
+        &self,
+        position: &Position,
+        moves: &mut [Move],
+        ply: usize,
+        best_move: Option<Move>,
+    ) {
+        moves.sort_by_cached_key(|r#move| -self.move_priority(position, *r#move, ply, best_move));
+    }
+
+    fn move_priority(
+        &self,
+        position: &Position,
+        r#move: Move,
+        ply: usize,
+        best_move: Option<Move>,
+    ) -> i32 {
+        if Some(r#move) == best_move {
+            return BEST_MOVE_PRIORITY;
+        }
+
+        if is_capture(position, r#move) {
+            // MVV-LVA (most valuable victim, least valuable attacker)
+            let victim = position
+                .piece_at(r#move.to_x, r#move.to_y)
+                .map_or(PieceKind::Pawn, |(_, kind)| kind);
+            let attacker = position
+                .piece_at(r#move.from_x, r#move.from_y)
+                .map_or(PieceKind::Pawn, |(_, kind)| kind);
+
+            return CAPTURE_PRIORITY + victim.index() as i32 * 8 - attacker.index() as i32;
+        }
+
+        if let Some(kind) = r#move.promotion {
+            return PROMOTION_PRIORITY + kind.index() as i32;
+        }
+
+        let killers = &self.killers[ply.min(MAX_PLY - 1)];
+
+        if killers[0] == Some(r#move) {
+            return KILLER_PRIORITY + 1;
+        }
+
+        if killers[1] == Some(r#move) {
+            return KILLER_PRIORITY;
+        }
+
+        self.history[from_index(r#move)][to_index(r#move)].min(KILLER_PRIORITY - 1)
+    }
+
+    fn store_killer(&mut self, r#move: Move, ply: usize) {
+        let killers = &mut self.killers[ply];
+
+        if killers[0] != Some(r#move) {
+            killers[1] = killers[0];
+            killers[0] = Some(r#move);
+        }
+    }
+}
+
+// Returns the time which may be spent on the next move
+pub fn time_for_move(
+    remaining_time: Duration,
+    increment: Duration,
+    moves_to_go: Option<u32>,
+) -> Duration {
+    let moves_to_go = moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+    let remaining_time = remaining_time.saturating_sub(MOVE_OVERHEAD);
+
+    // Never use more than the remaining time
+    (remaining_time / moves_to_go + increment * 3 / 4).min(remaining_time)
+}
+
+fn is_capture(position: &Position, r#move: Move) -> bool {
+    position.piece_at(r#move.to_x, r#move.to_y).is_some() || position.is_en_passant(r#move)
+}
+
+fn from_index(r#move: Move) -> usize {
+    r#move.from_y as usize * 8 + r#move.from_x as usize
+}
+
+fn to_index(r#move: Move) -> usize {
+    r#move.to_y as usize * 8 + r#move.to_x as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fen, uci};
+
+    fn position(fen: &str) -> Position {
+        let fen = fen::parse(fen).unwrap();
+
+        Position::new(&fen.pieces, fen.side_to_move, fen.en_passant_target)
+    }
+
+    fn search(fen: &str, max_depth: u32) -> SearchResult {
+        let mut transposition_table = TranspositionTable::new(1);
+        let mut search = Search::new(
+            Evaluation::default(),
+            &mut transposition_table,
+            Arc::new(AtomicBool::new(false)),
+        );
+
+        search.search(
+            &position(fen),
+            &[],
+            SearchLimits {
+                max_depth,
+                time_limit: None,
+            },
+        )
+    }
+
+    fn best_move(result: &SearchResult) -> String {
+        result.best_move.map_or(String::new(), uci::format_move)
+    }
+
+    #[test]
+    fn finds_mate_in_1() {
+        let result = search("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 4);
+
+        assert_eq!(best_move(&result), "a1a8");
+        assert_eq!(result.score, MATE_SCORE - 1);
+        // The search stops once the mate has been found
+        assert_eq!(result.depth, 1);
+    }
+
+    #[test]
+    fn finds_mate_in_2() {
+        // 1. Ra7 Kg8 2. Rb8# (or 1. Rb7 Kg8 2. Ra8#), there is no mate in 1
+        let result = search("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1", 6);
+
+        assert!(["a2a7", "b1b7"].contains(&best_move(&result).as_str()));
+        assert_eq!(result.score, MATE_SCORE - 3);
+    }
+
+    #[test]
+    fn is_mated() {
+        let result = search("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1", 4);
+
+        assert_eq!(result.best_move, None);
+        assert_eq!(result.score, -MATE_SCORE);
+    }
+
+    #[test]
+    fn does_not_leave_a_piece_hanging() {
+        // The knight is attacked by a pawn
+        let result = search("4k3/8/2p5/3N4/8/8/8/4K3 w - - 0 1", 3);
+        assert!(best_move(&result).starts_with("d5"));
+        assert!(result.score > 200, "{}", result.score);
+
+        // The pawn on d5 is defended, so the queen would be lost
+        let result = search("4k3/8/2p5/3p4/8/8/3Q4/4K3 w - - 0 1", 2);
+        assert_ne!(best_move(&result), "d2d5");
+        assert!(result.score > 500, "{}", result.score);
+    }
+
+    #[test]
+    fn time_for_move_stays_within_the_remaining_time() {
+        for remaining_time in [0, 10, 50, 51, 1_000, 60_000] {
+            for increment in [0, 100, 5_000, 120_000] {
+                for moves_to_go in [None, Some(0), Some(1), Some(40)] {
+                    let remaining_time = Duration::from_millis(remaining_time);
+                    let time = time_for_move(
+                        remaining_time,
+                        Duration::from_millis(increment),
+                        moves_to_go,
+                    );
+
+                    assert!(
+                        time <= remaining_time.saturating_sub(MOVE_OVERHEAD),
+                        "{remaining_time:?} {increment} {moves_to_go:?}: {time:?}"
+                    );
+                }
+            }
+        }
+
+        // The time is divided by the moves until the next time control
+        assert_eq!(
+            time_for_move(Duration::from_millis(10_050), Duration::ZERO, Some(10)),
+            Duration::from_secs(1)
+        );
+    }
+}
@@ -0,0 +1,282 @@
+use crate::{
+    evaluation::Evaluation,
+    rules::Position,
+    search::{Search, SearchLimits, SearchResult},
+    transposition_table::TranspositionTable,
+};
+use logger::*;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+enum SearchCommand {
+    Start {
+        id: u64,
+        position: Box<Position>,
+        position_hashes: Vec<u64>,
+        evaluation: Box<Evaluation>,
+        limits: SearchLimits,
+        stop: Arc<AtomicBool>,
+    },
+    // Resizes the transposition table (in MB)
+    SetHashSize(usize),
+    ClearHash,
+    Quit,
+}
+
+// Runs searches on a worker thread, so that the main loop keeps rendering while the engine is
+// thinking
+pub struct SearchService {
+    command_sender: Sender<SearchCommand>,
+    result_receiver: Receiver<(u64, SearchResult)>,
+    // Stop flag of the current search
+    stop: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+    // Identifies the latest search. Results of older searches are dropped
+    search_id: u64,
+    is_searching: bool,
+}
+
+impl SearchService {
+    // The transposition table is owned by the worker thread, so that it is kept between searches
+    pub fn new(hash_size_mb: usize) -> SearchService {
+        let (command_sender, command_receiver) = mpsc::channel();
+        let (result_sender, result_receiver) = mpsc::channel();
+
+        let worker = thread::Builder::new()
+            .name("search".to_string())
+            .spawn(move || work(hash_size_mb, command_receiver, result_sender))
+            .unwrap_or_else(|e| fatal!("Could not spawn search thread! ({})", e));
+
+        SearchService {
+            command_sender,
+            result_receiver,
+            stop: Arc::new(AtomicBool::new(false)),
+            worker: Some(worker),
+            search_id: 0,
+            is_searching: false,
+        }
+    }
+
+    // Starts a new search. A search which is still running is cancelled first
+    // position_hashes contains the hashes of all positions since the game started (including the
+    // current one)
+    pub fn start(
+        &mut self,
+        position: Position,
+        position_hashes: Vec<u64>,
+        evaluation: Evaluation,
+        limits: SearchLimits,
+    ) {
+        self.cancel();
+
+        self.search_id += 1;
+        self.stop = Arc::new(AtomicBool::new(false));
+
+        let command = SearchCommand::Start {
+            id: self.search_id,
+            position: Box::new(position),
+            position_hashes,
+            evaluation: Box::new(evaluation),
+            limits,
+            stop: self.stop.clone(),
+        };
+
+        if self.command_sender.send(command).is_err() {
+            error!("Could not start search! (the search thread has stopped)");
+            return;
+        }
+
+        self.is_searching = true;
+    }
+
+    // Stops the current search as soon as possible. Its (preliminary) result can still be polled
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    // Stops the current search. Its result is dropped
+    pub fn cancel(&mut self) {
+        if self.is_searching {
+            self.stop.store(true, Ordering::Relaxed);
+            self.is_searching = false;
+        }
+    }
+
+    // Takes effect after the current search
+    pub fn set_hash_size(&self, size_mb: usize) {
+        self.send(SearchCommand::SetHashSize(size_mb));
+    }
+
+    // Forgets the results of previous searches (e.g. for a new game)
+    pub fn clear_hash(&self) {
+        self.send(SearchCommand::ClearHash);
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.is_searching
+    }
+
+    // Returns the result of the current search once it is done (non-blocking)
+    pub fn poll(&mut self) -> Option<SearchResult> {
+        while let Ok((id, result)) = self.result_receiver.try_recv() {
+            if id == self.search_id && self.is_searching {
+                self.is_searching = false;
+
+                return Some(result);
+            }
+        }
+
+        None
+    }
+
+    fn send(&self, command: SearchCommand) {
+        if self.command_sender.send(command).is_err() {
+            error!("Could not send search command! (the search thread has stopped)");
+        }
+    }
+}
+
+impl Drop for SearchService {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.command_sender.send(SearchCommand::Quit);
+
+        if let Some(worker) = self.worker.take() {
+            if worker.join().is_err() {
+                error!("Could not join search thread!");
+            }
+        }
+    }
+}
+
+fn work(
+    hash_size_mb: usize,
+    command_receiver: Receiver<SearchCommand>,
+    result_sender: Sender<(u64, SearchResult)>,
+) {
+    let mut transposition_table = TranspositionTable::new(hash_size_mb);
+
+    while let Ok(command) = command_receiver.recv() {
+        match command {
+            SearchCommand::Start {
+                id,
+                position,
+                position_hashes,
+                evaluation,
+                limits,
+                stop,
+            } => {
+                let result = Search::new(*evaluation, &mut transposition_table, stop).search(
+                    &position,
+                    &position_hashes,
+                    limits,
+                );
+
+                if result_sender.send((id, result)).is_err() {
+                    return;
+                }
+            }
+            SearchCommand::SetHashSize(size_mb) => {
+                transposition_table = TranspositionTable::new(size_mb)
+            }
+            SearchCommand::ClearHash => transposition_table.clear(),
+            SearchCommand::Quit => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fen::{self, STARTING_FEN},
+        search::MAX_PLY,
+        uci,
+    };
+    use std::time::{Duration, Instant};
+
+    const MATE_IN_1: &str = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+
+    fn position(fen: &str) -> Position {
+        let fen = fen::parse(fen).unwrap();
+
+        Position::new(&fen.pieces, fen.side_to_move, fen.en_passant_target)
+    }
+
+    fn start(search_service: &mut SearchService, fen: &str, max_depth: u32) {
+        search_service.start(
+            position(fen),
+            Vec::new(),
+            Evaluation::default(),
+            SearchLimits {
+                max_depth,
+                time_limit: None,
+            },
+        );
+    }
+
+    // Polls until a result arrives (or a few seconds have passed)
+    fn wait_for_result(search_service: &mut SearchService) -> Option<SearchResult> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+
+        while Instant::now() < deadline {
+            if let Some(result) = search_service.poll() {
+                return Some(result);
+            }
+
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        None
+    }
+
+    #[test]
+    fn result_is_polled_once() {
+        let mut search_service = SearchService::new(1);
+        start(&mut search_service, MATE_IN_1, 2);
+        assert!(search_service.is_searching());
+
+        let result = wait_for_result(&mut search_service).unwrap();
+        assert_eq!(result.best_move.map(uci::format_move).unwrap(), "a1a8");
+        assert!(!search_service.is_searching());
+        assert!(search_service.poll().is_none());
+    }
+
+    #[test]
+    fn cancelled_search_is_never_polled() {
+        let mut search_service = SearchService::new(1);
+
+        // The worker sends the result of the cancelled search, which is dropped
+        start(&mut search_service, STARTING_FEN, MAX_PLY as u32);
+        search_service.cancel();
+        assert!(!search_service.is_searching());
+        thread::sleep(Duration::from_millis(100));
+        assert!(search_service.poll().is_none());
+
+        // Only the result of the latest search is returned (starting cancels the previous one)
+        start(&mut search_service, STARTING_FEN, MAX_PLY as u32);
+        start(&mut search_service, MATE_IN_1, 1);
+
+        let result = wait_for_result(&mut search_service).unwrap();
+        assert_eq!(result.best_move.map(uci::format_move).unwrap(), "a1a8");
+        assert!(search_service.poll().is_none());
+    }
+
+    #[test]
+    fn stopped_search_returns_a_best_move() {
+        let mut search_service = SearchService::new(1);
+        start(&mut search_service, STARTING_FEN, MAX_PLY as u32);
+        thread::sleep(Duration::from_millis(50));
+        search_service.stop();
+
+        let result = wait_for_result(&mut search_service).unwrap();
+        let best_move = result.best_move.unwrap();
+        assert!(position(STARTING_FEN).legal_moves().contains(&best_move));
+    }
+}
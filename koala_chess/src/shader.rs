@@ -1,3 +1,4 @@
+use crate::vec4::Vec4;
 use logger::*;
 use std::{error::Error, fs::read_to_string};
 
@@ -88,6 +89,22 @@ impl Shader {
         Ok(())
     }
 
+    pub fn set_vec4(&self, name: &str, value: Vec4) -> Result<(), Box<dyn Error>> {
+        let uniform_location = unsafe {
+            gl::GetUniformLocation(self.program, name.as_ptr() as *const gl::types::GLchar)
+        };
+
+        if uniform_location == -1 {
+            return Err(format!("Could not get uniform location! (name: {name})").into());
+        }
+
+        unsafe {
+            gl::Uniform4f(uniform_location, value[0], value[1], value[2], value[3]);
+        }
+
+        Ok(())
+    }
+
     pub fn set_mat4(
         &self,
         name: &str,
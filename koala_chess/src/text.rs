@@ -0,0 +1,210 @@
+use crate::{
+    bitmap,
+    mat4::Mat4,
+    shader::Shader,
+    transformations::{scale, translate},
+    vec3::Vec3,
+    vec4::Vec4,
+};
+use logger::*;
+use std::{
+    error::Error,
+    sync::{LazyLock, Mutex},
+};
+
+static TEXT_SHADER: LazyLock<Mutex<Option<Shader>>> = LazyLock::new(|| Mutex::new(None));
+static mut VERTEX_BUFFER_OBJECT: gl::types::GLuint = 0;
+static mut TEXTURE: gl::types::GLuint = 0;
+
+// A single line of text, drawn with the bitmap font (one textured quad per glyph)
+pub struct Text {
+    pub text: String,
+    // Top left corner
+    pub x: f32,
+    pub y: f32,
+    pub height: f32,
+    pub color: Vec4,
+}
+
+impl Text {
+    pub const TEXTURE_ATLAS_SIZE: i32 = 512;
+    // The glyphs are stored in 16 x 8 cells of 32 x 64 pixels (see textures/README.md)
+    pub const GLYPH_COLUMNS: u8 = 16;
+    // Monospaced font, so every glyph has the same width
+    pub const GLYPH_ASPECT_RATIO: f32 = 0.5;
+
+    pub fn initialize(text_shader: Shader) {
+        *TEXT_SHADER
+            .lock()
+            .unwrap_or_else(|e| fatal!("Could not lock text shader mutex! {}", e)) =
+            Some(text_shader);
+
+        // Load bitmap
+        let bitmap = bitmap::load_bitmap("textures/font.bmp")
+            .unwrap_or_else(|e| fatal!("Could not load font bitmap! ({})", e));
+
+        #[rustfmt::skip]
+        let vertices: [f32; 16] = [
+            // positions, texture coordinates
+            0.0, 0.0,     0.0, 0.0, // top left
+            1.0, 0.0,     1.0, 0.0, // top right
+            1.0, 1.0,     1.0, 1.0, // bottom right
+            0.0, 1.0,     0.0, 1.0, // bottom left
+        ];
+
+        unsafe {
+            // Generate vertex buffer object
+            gl::GenBuffers(1, std::ptr::addr_of_mut!(VERTEX_BUFFER_OBJECT));
+
+            // Bind vertex buffer object
+            gl::BindBuffer(gl::ARRAY_BUFFER, VERTEX_BUFFER_OBJECT);
+
+            // Set vertex buffer object data
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                std::mem::size_of_val(&vertices) as gl::types::GLsizeiptr,
+                vertices.as_ptr() as *const std::ffi::c_void,
+                gl::STATIC_DRAW,
+            );
+
+            // Generate texture
+            gl::GenTextures(1, std::ptr::addr_of_mut!(TEXTURE));
+
+            // Bind texture
+            gl::BindTexture(gl::TEXTURE_2D, TEXTURE);
+
+            // Parameterize texture. The glyphs are drawn a lot smaller than they are stored, so
+            // mipmaps are used
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_MIN_FILTER,
+                gl::LINEAR_MIPMAP_LINEAR as gl::types::GLint,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_MAG_FILTER,
+                gl::LINEAR as gl::types::GLint,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_WRAP_S,
+                gl::CLAMP_TO_EDGE as gl::types::GLint,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_WRAP_T,
+                gl::CLAMP_TO_EDGE as gl::types::GLint,
+            );
+
+            // Setup texture
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA8 as gl::types::GLint,
+                Text::TEXTURE_ATLAS_SIZE,
+                Text::TEXTURE_ATLAS_SIZE,
+                0,
+                gl::BGRA_EXT,
+                gl::UNSIGNED_BYTE,
+                bitmap.data.as_ptr() as *const std::ffi::c_void,
+            );
+
+            // Generate mipmap
+            gl::GenerateMipmap(gl::TEXTURE_2D);
+        }
+    }
+
+    pub fn new(text: impl Into<String>, x: f32, y: f32, height: f32, color: Vec4) -> Text {
+        Text {
+            text: text.into(),
+            x,
+            y,
+            height,
+            color,
+        }
+    }
+
+    pub fn glyph_width(&self) -> f32 {
+        self.height * Text::GLYPH_ASPECT_RATIO
+    }
+
+    pub fn width(&self) -> f32 {
+        self.text.chars().count() as f32 * self.glyph_width()
+    }
+
+    pub fn draw(&self, projection: &Mat4) -> Result<(), Box<dyn Error>> {
+        unsafe {
+            // Bind vertex buffer object
+            gl::BindBuffer(gl::ARRAY_BUFFER, VERTEX_BUFFER_OBJECT);
+
+            // Position attribute
+            gl::VertexAttribPointer(
+                0,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                16,
+                std::ptr::null::<std::ffi::c_void>(),
+            );
+            gl::EnableVertexAttribArray(0);
+
+            // Texture coordinates attribute
+            gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, 16, 8 as *const std::ffi::c_void);
+            gl::EnableVertexAttribArray(1);
+
+            // Bind texture
+            gl::BindTexture(gl::TEXTURE_2D, TEXTURE);
+        }
+
+        // Use specific shader
+        let text_shader_mutex = TEXT_SHADER
+            .lock()
+            .unwrap_or_else(|e| fatal!("Could not lock text shader mutex! {}", e));
+        let text_shader = text_shader_mutex
+            .unwrap_or_else(|| fatal!("Text shader has not been initialized yet!"));
+        text_shader.r#use();
+
+        text_shader.set_mat4(
+            "projection\0",
+            projection.data.as_ptr() as *const gl::types::GLfloat,
+        )?;
+        text_shader.set_vec4("color\0", self.color)?;
+
+        let glyph_width = self.glyph_width();
+
+        for (index, c) in self.text.chars().enumerate() {
+            // Spaces do not have to be drawn
+            if c == ' ' {
+                continue;
+            }
+
+            // The atlas only contains printable ASCII characters
+            let code = if c.is_ascii_graphic() { c as u8 } else { b'?' };
+
+            // Calculate model
+            let mut model = Mat4::identity();
+            model = translate(
+                model,
+                Vec3::new_xyz(self.x + index as f32 * glyph_width, self.y, 0.0),
+            );
+            model = scale(model, Vec3::new_xyz(glyph_width, self.height, 1.0));
+
+            text_shader.set_mat4("model\0", model.data.as_ptr() as *const gl::types::GLfloat)?;
+            text_shader.set_float(
+                "glyph_x\0",
+                (code % Text::GLYPH_COLUMNS) as gl::types::GLfloat,
+            )?;
+            text_shader.set_float(
+                "glyph_y\0",
+                (code / Text::GLYPH_COLUMNS) as gl::types::GLfloat,
+            )?;
+
+            // Draw elements
+            unsafe {
+                gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, std::ptr::null());
+            }
+        }
+
+        Ok(())
+    }
+}
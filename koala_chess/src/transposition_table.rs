@@ -0,0 +1,222 @@
+use crate::{
+    rules::Move,
+    search::{MATE_SCORE, MAX_PLY},
+};
+use std::mem;
+
+pub const DEFAULT_SIZE_MB: usize = 16;
+pub const MAX_SIZE_MB: usize = 1024;
+
+// Relation between the stored score and the actual score of the position
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Bound {
+    Exact,
+    // The actual score is at least the stored score (beta cutoff)
+    Lower,
+    // The actual score is at most the stored score (no move raised alpha)
+    Upper,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Entry {
+    key: u64,
+    pub best_move: Option<Move>,
+    score: i32,
+    pub depth: u32,
+    pub bound: Bound,
+    // Search the entry was stored in
+    age: u8,
+}
+
+impl Entry {
+    // Mate scores are stored relative to the position (instead of the root), because the same
+    // position can be reached at different plies
+    pub fn score(&self, ply: usize) -> i32 {
+        if self.score >= MATE_SCORE - MAX_PLY as i32 {
+            self.score - ply as i32
+        } else if self.score <= -MATE_SCORE + MAX_PLY as i32 {
+            self.score + ply as i32
+        } else {
+            self.score
+        }
+    }
+}
+
+// Fixed-size hash table of search results, indexed by the Zobrist hash of the position
+// Reference: https://www.chessprogramming.org/Transposition_Table
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+    age: u8,
+}
+
+impl TranspositionTable {
+    pub fn new(size_mb: usize) -> TranspositionTable {
+        let size_mb = size_mb.clamp(1, MAX_SIZE_MB);
+        let length = size_mb * 1024 * 1024 / mem::size_of::<Option<Entry>>();
+
+        TranspositionTable {
+            entries: vec![None; length],
+            age: 0,
+        }
+    }
+
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        self.entries[self.index(key)].filter(|entry| entry.key == key)
+    }
+
+    pub fn store(
+        &mut self,
+        key: u64,
+        best_move: Option<Move>,
+        score: i32,
+        depth: u32,
+        bound: Bound,
+        ply: usize,
+    ) {
+        let index = self.index(key);
+
+        // Replace entries of other positions only if they are from an earlier search or not
+        // deeper than the new one
+        let replace = match self.entries[index] {
+            None => true,
+            Some(entry) => entry.key == key || entry.age != self.age || entry.depth <= depth,
+        };
+
+        if !replace {
+            return;
+        }
+
+        // Keep the best move of the same position if there is no new one
+        let best_move = best_move.or_else(|| {
+            self.entries[index]
+                .filter(|entry| entry.key == key)
+                .and_then(|entry| entry.best_move)
+        });
+
+        let score = if score >= MATE_SCORE - MAX_PLY as i32 {
+            score + ply as i32
+        } else if score <= -MATE_SCORE + MAX_PLY as i32 {
+            score - ply as i32
+        } else {
+            score
+        };
+
+        self.entries[index] = Some(Entry {
+            key,
+            best_move,
+            score,
+            depth,
+            bound,
+            age: self.age,
+        });
+    }
+
+    // Has to be called before each search, so that old entries are replaced first
+    pub fn new_search(&mut self) {
+        self.age = self.age.wrapping_add(1);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+        self.age = 0;
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key % self.entries.len() as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: u64 = 12_345;
+
+    fn r#move(from_x: u8, to_x: u8) -> Move {
+        Move {
+            from_x,
+            from_y: 0,
+            to_x,
+            to_y: 0,
+            promotion: None,
+        }
+    }
+
+    // Another key which is stored at the same index
+    fn colliding_key(transposition_table: &TranspositionTable) -> u64 {
+        KEY + transposition_table.entries.len() as u64
+    }
+
+    #[test]
+    fn mate_scores_keep_the_distance_to_mate() {
+        let mut transposition_table = TranspositionTable::new(1);
+
+        // Mate in 5 plies from the root, found at ply 2 (3 plies from the position)
+        transposition_table.store(KEY, None, MATE_SCORE - 5, 4, Bound::Exact, 2);
+        let entry = transposition_table.probe(KEY).unwrap();
+        assert_eq!(entry.score(2), MATE_SCORE - 5);
+        assert_eq!(entry.score(6), MATE_SCORE - 9);
+        assert_eq!(entry.score(0), MATE_SCORE - 3);
+
+        // Being mated
+        transposition_table.store(KEY, None, -MATE_SCORE + 4, 4, Bound::Exact, 3);
+        let entry = transposition_table.probe(KEY).unwrap();
+        assert_eq!(entry.score(3), -MATE_SCORE + 4);
+        assert_eq!(entry.score(1), -MATE_SCORE + 2);
+
+        // Other scores do not depend on the ply
+        transposition_table.store(KEY, None, 250, 4, Bound::Lower, 7);
+        let entry = transposition_table.probe(KEY).unwrap();
+        assert_eq!(entry.score(0), 250);
+        assert_eq!(entry.score(20), 250);
+        assert_eq!(entry.bound, Bound::Lower);
+    }
+
+    #[test]
+    fn probe_checks_the_key() {
+        let mut transposition_table = TranspositionTable::new(1);
+        transposition_table.store(KEY, Some(r#move(0, 1)), 10, 3, Bound::Exact, 0);
+
+        assert!(transposition_table.probe(KEY).is_some());
+        assert!(transposition_table
+            .probe(colliding_key(&transposition_table))
+            .is_none());
+
+        transposition_table.clear();
+        assert!(transposition_table.probe(KEY).is_none());
+    }
+
+    #[test]
+    fn replacement_policy() {
+        let mut transposition_table = TranspositionTable::new(1);
+        let other_key = colliding_key(&transposition_table);
+        transposition_table.new_search();
+        transposition_table.store(KEY, Some(r#move(0, 1)), 10, 6, Bound::Exact, 0);
+
+        // A shallower entry of another position does not replace a deeper one of the same search
+        transposition_table.store(other_key, None, 20, 5, Bound::Exact, 0);
+        assert!(transposition_table.probe(KEY).is_some());
+        assert!(transposition_table.probe(other_key).is_none());
+
+        // The same position is always replaced. Its best move is kept if there is no new one
+        transposition_table.store(KEY, None, 30, 2, Bound::Upper, 0);
+        let entry = transposition_table.probe(KEY).unwrap();
+        assert_eq!(entry.depth, 2);
+        assert_eq!(entry.best_move, Some(r#move(0, 1)));
+
+        // An entry which is at least as deep replaces another position
+        transposition_table.store(other_key, None, 20, 2, Bound::Exact, 0);
+        assert!(transposition_table.probe(KEY).is_none());
+        assert_eq!(
+            transposition_table.probe(other_key).unwrap().best_move,
+            None
+        );
+
+        // Entries of an earlier search are replaced regardless of their depth
+        transposition_table.store(KEY, None, 10, 20, Bound::Exact, 0);
+        transposition_table.new_search();
+        transposition_table.store(other_key, None, 20, 1, Bound::Exact, 0);
+        assert!(transposition_table.probe(KEY).is_none());
+        assert!(transposition_table.probe(other_key).is_some());
+    }
+}
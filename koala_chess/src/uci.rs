@@ -0,0 +1,518 @@
+use crate::{
+    evaluation::Evaluation,
+    fen::{self, STARTING_FEN},
+    game::Game,
+    piece::{PieceColor, PieceKind},
+    rules::{Move, Position},
+    san,
+    search::{time_for_move, SearchLimits, SearchResult, MATE_SCORE, MAX_PLY, MOVE_OVERHEAD},
+    search_service::SearchService,
+    transposition_table::{DEFAULT_SIZE_MB, MAX_SIZE_MB},
+};
+use logger::*;
+use std::{
+    error::Error,
+    io::{self, BufRead, Write},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+// Time between two polls of the search result
+pub const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+// Universal Chess Interface (stdin/stdout), used by GUIs like cutechess or Arena
+// Reference: https://backscattering.de/chess/uci/
+pub fn run() {
+    // Only errors are logged (to stderr), because stdout belongs to the protocol
+    set_log_level(LogLevel::Error);
+
+    let line_receiver = spawn_stdin_reader();
+    let mut uci = Uci::new();
+
+    loop {
+        match line_receiver.recv_timeout(POLL_INTERVAL) {
+            Ok(line) => {
+                if !uci.handle_command(&line) {
+                    break;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => (),
+            // stdin has been closed
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        uci.poll_search();
+    }
+}
+
+// Reads stdin on a separate thread, so that commands (e.g. "stop") can be received while searching
+pub fn spawn_stdin_reader() -> Receiver<String> {
+    let (line_sender, line_receiver) = mpsc::channel();
+
+    thread::Builder::new()
+        .name("stdin".to_string())
+        .spawn(move || {
+            for line in io::stdin().lock().lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+
+                if line_sender.send(line).is_err() {
+                    break;
+                }
+            }
+        })
+        .unwrap_or_else(|e| fatal!("Could not spawn stdin thread! ({})", e));
+
+    line_receiver
+}
+
+struct Uci {
+    // None after an invalid position, until a valid one arrives (nothing is searched meanwhile)
+    game: Option<Game>,
+    evaluation: Evaluation,
+    search_service: SearchService,
+    // The best move of an infinite search may only be sent after "stop", even if the search has
+    // ended before (e.g. because a mate has been found)
+    infinite: bool,
+    held_result: Option<SearchResult>,
+}
+
+impl Uci {
+    fn new() -> Uci {
+        Uci {
+            game: Some(Game::new()),
+            evaluation: Evaluation::default(),
+            search_service: SearchService::new(DEFAULT_SIZE_MB),
+            infinite: false,
+            held_result: None,
+        }
+    }
+
+    // Returns false if the engine should quit
+    fn handle_command(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+
+        let Some((command, arguments)) = tokens.split_first() else {
+            return true;
+        };
+
+        match *command {
+            "uci" => {
+                send("id name Koala Chess");
+                send("id author Oliver Piorun");
+                send(&format!(
+                    "option name Hash type spin default {DEFAULT_SIZE_MB} min 1 max {MAX_SIZE_MB}"
+                ));
+                send("uciok");
+            }
+            "isready" => send("readyok"),
+            "ucinewgame" => {
+                self.cancel_search();
+                self.search_service.clear_hash();
+                self.game = Some(Game::new());
+            }
+            "position" => {
+                self.cancel_search();
+
+                // The previous position must not be searched instead
+                self.game = parse_position(arguments)
+                    .inspect_err(|e| error!("{}", e))
+                    .ok();
+            }
+            "go" => self.go(arguments),
+            "stop" => self.stop(),
+            "setoption" => self.set_option(arguments),
+            "quit" => return false,
+            // Pondering, debug mode and registration are not supported
+            "ponderhit" | "debug" | "register" => (),
+            _ => error!("Unknown UCI command! ({})", line),
+        }
+
+        true
+    }
+
+    fn go(&mut self, arguments: &[&str]) {
+        let Some(game) = &self.game else {
+            // The GUI waits for a best move in any case
+            error!("Cannot search without a valid position!");
+            send("bestmove 0000");
+            return;
+        };
+
+        let limits = parse_limits(arguments, game.side_to_move);
+        self.infinite = arguments.contains(&"infinite");
+        self.held_result = None;
+
+        self.search_service.start(
+            game.position(),
+            game.position_hashes.clone(),
+            self.evaluation.clone(),
+            limits,
+        );
+    }
+
+    fn set_option(&mut self, arguments: &[&str]) {
+        let (name, value) = parse_option(arguments);
+
+        match name.as_str() {
+            "Hash" => match value.parse::<usize>() {
+                Ok(size_mb) => self.search_service.set_hash_size(size_mb),
+                Err(_) => error!("Invalid UCI option value! ({} = {})", name, value),
+            },
+            _ => error!("Unknown UCI option! ({} = {})", name, value),
+        }
+    }
+
+    // Sends the best move of the current search (immediately, if an infinite search has already
+    // ended)
+    fn stop(&mut self) {
+        self.infinite = false;
+
+        match self.held_result.take() {
+            Some(result) => self.send_result(&result),
+            None => self.search_service.stop(),
+        }
+    }
+
+    // Drops the current search and its result
+    fn cancel_search(&mut self) {
+        self.search_service.cancel();
+        self.infinite = false;
+        self.held_result = None;
+    }
+
+    fn poll_search(&mut self) {
+        if let Some(result) = self.search_service.poll() {
+            if self.infinite {
+                self.held_result = Some(result);
+            } else {
+                self.send_result(&result);
+            }
+        }
+    }
+
+    fn send_result(&self, result: &SearchResult) {
+        // Results of cancelled searches are dropped, so there is a game for every result
+        if let Some(game) = &self.game {
+            send_result(&game.position(), result);
+        }
+    }
+}
+
+pub fn send(message: &str) {
+    let mut stdout = io::stdout().lock();
+
+    if writeln!(stdout, "{message}")
+        .and_then(|()| stdout.flush())
+        .is_err()
+    {
+        error!("Could not write to stdout! ({})", message);
+    }
+}
+
+fn send_result(position: &Position, result: &SearchResult) {
+    let score = format_score(result.score);
+    let best_move = result.best_move.map_or("0000".to_string(), format_move);
+
+    send(&format!(
+        "info depth {} score {} nodes {} pv {}",
+        result.depth, score, result.nodes, best_move
+    ));
+
+    if let Some(r#move) = result.best_move {
+        debug!("Best move: {}", san::format(position, r#move));
+    }
+
+    send(&format!("bestmove {best_move}"));
+}
+
+// Mate scores are sent in moves instead of centipawns
+fn format_score(score: i32) -> String {
+    if score.abs() >= MATE_SCORE - MAX_PLY as i32 {
+        let plies = MATE_SCORE - score.abs();
+        let moves = (plies + 1) / 2;
+
+        format!("mate {}", score.signum() * moves)
+    } else {
+        format!("cp {score}")
+    }
+}
+
+// position [startpos | fen <fen>] [moves <move> ...]
+fn parse_position(arguments: &[&str]) -> Result<Game, Box<dyn Error>> {
+    let moves_index = arguments
+        .iter()
+        .position(|argument| *argument == "moves")
+        .unwrap_or(arguments.len());
+
+    let mut game = match arguments.first() {
+        Some(&"startpos") => Game::from_fen(STARTING_FEN)?,
+        Some(&"fen") => Game::from_fen(&arguments[1..moves_index].join(" "))?,
+        _ => return Err(format!("Invalid UCI position! ({})", arguments.join(" ")).into()),
+    };
+
+    for text in arguments.iter().skip(moves_index + 1) {
+        let r#move = parse_move(&game.position(), text)?;
+
+        game.make_move(r#move)?;
+    }
+
+    Ok(game)
+}
+
+// go [wtime <ms>] [btime <ms>] [winc <ms>] [binc <ms>] [movestogo <n>] [depth <n>]
+// [movetime <ms>] [infinite]
+fn parse_limits(arguments: &[&str], side_to_move: PieceColor) -> SearchLimits {
+    let value = |name: &str| -> Option<u64> {
+        let index = arguments.iter().position(|argument| *argument == name)?;
+
+        arguments.get(index + 1)?.parse().ok()
+    };
+
+    let (time, increment) = match side_to_move {
+        PieceColor::White => (value("wtime"), value("winc")),
+        PieceColor::Black => (value("btime"), value("binc")),
+    };
+
+    let time_limit = if arguments.contains(&"infinite") {
+        None
+    } else if let Some(move_time) = value("movetime") {
+        Some(Duration::from_millis(move_time).saturating_sub(MOVE_OVERHEAD))
+    } else {
+        time.map(|time| {
+            time_for_move(
+                Duration::from_millis(time),
+                Duration::from_millis(increment.unwrap_or(0)),
+                value("movestogo").map(|moves_to_go| moves_to_go as u32),
+            )
+        })
+    };
+
+    SearchLimits {
+        max_depth: value("depth").map_or(MAX_PLY as u32, |depth| {
+            depth.clamp(1, MAX_PLY as u64) as u32
+        }),
+        time_limit,
+    }
+}
+
+// setoption name <name> [value <value>]
+fn parse_option(arguments: &[&str]) -> (String, String) {
+    let value_index = arguments
+        .iter()
+        .position(|argument| *argument == "value")
+        .unwrap_or(arguments.len());
+
+    let name = arguments
+        .get(1..value_index)
+        .map_or(String::new(), |name| name.join(" "));
+    let value = arguments
+        .get(value_index + 1..)
+        .map_or(String::new(), |value| value.join(" "));
+
+    (name, value)
+}
+
+// Long algebraic notation (e.g. e2e4, e7e8q)
+pub fn format_move(r#move: Move) -> String {
+    let mut text = format!(
+        "{}{}",
+        fen::format_square(r#move.from_x, r#move.from_y),
+        fen::format_square(r#move.to_x, r#move.to_y)
+    );
+
+    if let Some(kind) = r#move.promotion {
+        text.push(san::piece_letter(kind).to_ascii_lowercase());
+    }
+
+    text
+}
+
+pub fn parse_move(position: &Position, text: &str) -> Result<Move, Box<dyn Error>> {
+    let invalid_move = || format!("Invalid UCI move '{text}'!");
+
+    if !text.is_ascii() || !(4..=5).contains(&text.len()) {
+        return Err(invalid_move().into());
+    }
+
+    let (from_x, from_y) = fen::parse_square(&text[0..2]).ok_or_else(invalid_move)?;
+    let (to_x, to_y) = fen::parse_square(&text[2..4]).ok_or_else(invalid_move)?;
+    let promotion = match text[4..].chars().next() {
+        Some(c) => Some(
+            san::parse_piece_letter(c.to_ascii_uppercase())
+                .filter(|kind| *kind != PieceKind::Pawn && *kind != PieceKind::King)
+                .ok_or_else(invalid_move)?,
+        ),
+        None => None,
+    };
+
+    let r#move = Move {
+        from_x,
+        from_y,
+        to_x,
+        to_y,
+        promotion,
+    };
+
+    if !position.legal_moves().contains(&r#move) {
+        return Err(format!("Illegal UCI move '{text}'!").into());
+    }
+
+    Ok(r#move)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    const MATE_IN_1: &str = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+
+    fn tokens(text: &str) -> Vec<&str> {
+        text.split_whitespace().collect()
+    }
+
+    #[test]
+    fn position_startpos_with_moves() {
+        let game = parse_position(&tokens("startpos moves e2e4 e7e5 g1f3")).unwrap();
+
+        assert_eq!(game.history.len(), 3);
+        assert_eq!(
+            game.to_fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+        );
+
+        let game = parse_position(&tokens("startpos")).unwrap();
+        assert_eq!(game.to_fen(), STARTING_FEN);
+    }
+
+    #[test]
+    fn position_fen_with_moves() {
+        let game = parse_position(&tokens(
+            "fen 4k3/P7/8/8/8/8/8/4K3 w - - 0 1 moves a7a8q e8d7",
+        ))
+        .unwrap();
+
+        assert_eq!(game.history.len(), 2);
+        assert_eq!(game.to_fen(), "Q7/3k4/8/8/8/8/8/4K3 w - - 1 2");
+    }
+
+    #[test]
+    fn position_errors() {
+        for (arguments, expected_error) in [
+            ("startpos moves e2e4 e2e4", "Illegal UCI move 'e2e4'"),
+            ("startpos moves e2e9", "Invalid UCI move 'e2e9'"),
+            ("startpos moves e7e8k", "Invalid UCI move 'e7e8k'"),
+            ("fen 8/8/8/8/8/8/8/8 w - - 0 1", "Invalid FEN"),
+            ("", "Invalid UCI position"),
+            ("somewhere", "Invalid UCI position"),
+        ] {
+            let error = parse_position(&tokens(arguments))
+                .err()
+                .map(|e| e.to_string())
+                .unwrap_or_default();
+
+            assert!(error.contains(expected_error), "{arguments}: {error}");
+        }
+    }
+
+    #[test]
+    fn limits() {
+        let arguments = tokens("wtime 60000 btime 30000 winc 1000 binc 0 movestogo 20");
+
+        let limits = parse_limits(&arguments, PieceColor::White);
+        assert_eq!(limits.max_depth, MAX_PLY as u32);
+        assert_eq!(
+            limits.time_limit,
+            Some(time_for_move(
+                Duration::from_secs(60),
+                Duration::from_secs(1),
+                Some(20)
+            ))
+        );
+
+        let limits = parse_limits(&arguments, PieceColor::Black);
+        assert_eq!(
+            limits.time_limit,
+            Some(time_for_move(
+                Duration::from_secs(30),
+                Duration::ZERO,
+                Some(20)
+            ))
+        );
+
+        // The move time takes precedence over the clock times
+        let limits = parse_limits(&tokens("wtime 60000 movetime 1000"), PieceColor::White);
+        assert_eq!(
+            limits.time_limit,
+            Some(Duration::from_millis(1000) - MOVE_OVERHEAD)
+        );
+
+        let limits = parse_limits(&tokens("infinite movetime 1000"), PieceColor::White);
+        assert_eq!(limits.time_limit, None);
+
+        let limits = parse_limits(&tokens("depth 5"), PieceColor::White);
+        assert_eq!(limits.max_depth, 5);
+        assert_eq!(limits.time_limit, None);
+
+        assert_eq!(
+            parse_limits(&tokens("depth 0"), PieceColor::White).max_depth,
+            1
+        );
+        assert_eq!(
+            parse_limits(&tokens("depth 1000"), PieceColor::White).max_depth,
+            MAX_PLY as u32
+        );
+    }
+
+    #[test]
+    fn options() {
+        assert_eq!(
+            parse_option(&tokens("name Hash value 64")),
+            ("Hash".to_string(), "64".to_string())
+        );
+        assert_eq!(
+            parse_option(&tokens("name Clear Hash")),
+            ("Clear Hash".to_string(), String::new())
+        );
+        assert_eq!(
+            parse_option(&tokens("name Syzygy Path value /tb/a b")),
+            ("Syzygy Path".to_string(), "/tb/a b".to_string())
+        );
+    }
+
+    #[test]
+    fn scores() {
+        assert_eq!(format_score(35), "cp 35");
+        assert_eq!(format_score(-120), "cp -120");
+        assert_eq!(format_score(MATE_SCORE - 1), "mate 1");
+        assert_eq!(format_score(MATE_SCORE - 3), "mate 2");
+        assert_eq!(format_score(-(MATE_SCORE - 2)), "mate -1");
+        assert_eq!(format_score(-MATE_SCORE), "mate 0");
+    }
+
+    #[test]
+    fn infinite_search_waits_for_stop() {
+        let mut uci = Uci::new();
+        uci.handle_command(&format!("position fen {MATE_IN_1}"));
+        uci.handle_command("go infinite");
+
+        // The search ends by itself after finding the mate
+        let deadline = Instant::now() + Duration::from_secs(10);
+
+        while uci.held_result.is_none() && Instant::now() < deadline {
+            uci.poll_search();
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        let result = uci.held_result.unwrap();
+        assert_eq!(result.best_move.map(format_move).unwrap(), "a1a8");
+
+        // A new position drops the result
+        uci.handle_command("position startpos");
+        assert!(uci.held_result.is_none());
+        assert!(!uci.infinite);
+    }
+}
@@ -60,4 +60,19 @@ impl Vec4 {
 
         result
     }
+
+    pub fn new_xyzw(
+        x: gl::types::GLfloat,
+        y: gl::types::GLfloat,
+        z: gl::types::GLfloat,
+        w: gl::types::GLfloat,
+    ) -> Vec4 {
+        let mut result = Vec4::default();
+        result[0] = x;
+        result[1] = y;
+        result[2] = z;
+        result[3] = w;
+
+        result
+    }
 }
@@ -0,0 +1,496 @@
+use crate::{
+    evaluation::Evaluation,
+    game::Game,
+    pgn,
+    piece::PieceColor,
+    rules::Move,
+    san,
+    search::{time_for_move, SearchLimits, SearchResult, MATE_SCORE, MAX_PLY, MOVE_OVERHEAD},
+    search_service::SearchService,
+    transposition_table::DEFAULT_SIZE_MB,
+    uci::{self, send, spawn_stdin_reader, POLL_INTERVAL},
+};
+use logger::*;
+use std::{
+    error::Error,
+    sync::mpsc::RecvTimeoutError,
+    time::{Duration, Instant},
+};
+
+// Chess Engine Communication Protocol (stdin/stdout), used by XBoard/WinBoard
+// Reference: https://www.gnu.org/software/xboard/engine-intf.html
+pub fn run() {
+    // Only errors are logged (to stderr), because stdout belongs to the protocol
+    set_log_level(LogLevel::Error);
+
+    let line_receiver = spawn_stdin_reader();
+    let mut xboard = XBoard::new();
+
+    loop {
+        match line_receiver.recv_timeout(POLL_INTERVAL) {
+            Ok(line) => {
+                if !xboard.handle_command(&line) {
+                    break;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => (),
+            // stdin has been closed
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        xboard.poll_search();
+        xboard.start_search_if_engine_to_move();
+    }
+}
+
+// Mates are sent as 100000 + N (mate in N moves) or -100000 - N (mated in N moves)
+const CECP_MATE_SCORE: i32 = 100_000;
+
+// Time control as set by "level" (and "st")
+struct TimeControl {
+    // 0 means that the base time is used for the whole game
+    moves_per_session: u32,
+    base_time: Duration,
+    increment: Duration,
+    // Fixed time per move
+    move_time: Option<Duration>,
+}
+
+struct XBoard {
+    game: Game,
+    evaluation: Evaluation,
+    search_service: SearchService,
+    // In force mode the engine only checks the moves of both sides
+    force: bool,
+    engine_color: PieceColor,
+    time_control: TimeControl,
+    // Remaining time of the engine (sent via "time" before each move)
+    remaining_time: Option<Duration>,
+    max_depth: u32,
+    // Whether to send thinking output
+    post: bool,
+    // Start of the current search (for the thinking output)
+    search_start: Instant,
+}
+
+impl XBoard {
+    fn new() -> XBoard {
+        XBoard {
+            game: Game::new(),
+            evaluation: Evaluation::default(),
+            search_service: SearchService::new(DEFAULT_SIZE_MB),
+            force: false,
+            engine_color: PieceColor::Black,
+            time_control: TimeControl {
+                moves_per_session: 0,
+                base_time: Duration::from_secs(300),
+                increment: Duration::ZERO,
+                move_time: None,
+            },
+            remaining_time: None,
+            max_depth: MAX_PLY as u32,
+            post: false,
+            search_start: Instant::now(),
+        }
+    }
+
+    // Returns false if the engine should quit
+    fn handle_command(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+
+        let Some((command, arguments)) = tokens.split_first() else {
+            return true;
+        };
+
+        match *command {
+            "xboard" | "accepted" | "rejected" => (),
+            "protover" => send(
+                "feature myname=\"Koala Chess\" usermove=1 setboard=1 ping=1 playother=1 \
+                 colors=0 sigint=0 sigterm=0 analyze=0 memory=1 done=1",
+            ),
+            "new" => {
+                self.search_service.cancel();
+                self.search_service.clear_hash();
+                self.game = Game::new();
+                self.force = false;
+                self.engine_color = PieceColor::Black;
+                self.remaining_time = None;
+                self.max_depth = MAX_PLY as u32;
+            }
+            "setboard" => {
+                self.search_service.cancel();
+
+                match Game::from_fen(&arguments.join(" ")) {
+                    Ok(game) => self.game = game,
+                    Err(e) => send(&format!("tellusererror Illegal position: {e}")),
+                }
+            }
+            "force" => {
+                self.search_service.cancel();
+                self.force = true;
+            }
+            "go" => {
+                self.force = false;
+                self.engine_color = self.game.side_to_move;
+            }
+            "playother" => {
+                self.force = false;
+                self.engine_color = self.game.side_to_move.opposite();
+            }
+            "usermove" => self.user_move(arguments.first().copied().unwrap_or_default()),
+            // Move now
+            "?" => self.search_service.stop(),
+            "level" => self.level(arguments),
+            // Negative, infinite or too large times are rejected
+            "st" => match arguments
+                .first()
+                .and_then(|seconds| seconds.parse::<f32>().ok())
+                .and_then(|seconds| Duration::try_from_secs_f32(seconds).ok())
+            {
+                Some(move_time) => self.time_control.move_time = Some(move_time),
+                None => error!("Invalid st command! ({})", line),
+            },
+            "sd" => match arguments
+                .first()
+                .and_then(|depth| depth.parse::<u32>().ok())
+            {
+                Some(depth) => self.max_depth = depth.clamp(1, MAX_PLY as u32),
+                None => error!("Invalid sd command! ({})", line),
+            },
+            // Memory for the hash tables in MB
+            "memory" => match arguments
+                .first()
+                .and_then(|size_mb| size_mb.parse::<usize>().ok())
+            {
+                Some(size_mb) => self.search_service.set_hash_size(size_mb),
+                None => error!("Invalid memory command! ({})", line),
+            },
+            // The remaining time is sent in centiseconds
+            "time" => {
+                self.remaining_time = arguments
+                    .first()
+                    .and_then(|time| time.parse::<u64>().ok())
+                    .map(|time| Duration::from_millis(time.saturating_mul(10)))
+            }
+            "undo" => self.undo(1),
+            "remove" => self.undo(2),
+            "result" => {
+                self.search_service.cancel();
+                self.force = true;
+            }
+            "ping" => send(&format!("pong {}", arguments.join(" "))),
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "quit" => return false,
+            // Not supported or not needed
+            "random" | "hard" | "easy" | "computer" | "name" | "otim" | "draw" | "white"
+            | "black" => (),
+            _ => send(&format!("Error (unknown command): {line}")),
+        }
+
+        true
+    }
+
+    fn user_move(&mut self, text: &str) {
+        // Coordinate notation (e.g. e2e4) is expected, but SAN is accepted as well
+        let position = self.game.position();
+        let r#move = uci::parse_move(&position, text).or_else(|_| san::parse(&position, text));
+
+        match r#move.and_then(|r#move| self.game.make_move(r#move)) {
+            Ok(()) => self.send_result_if_over(),
+            Err(e) => {
+                debug!("{}", e);
+                send(&format!("Illegal move: {text}"));
+            }
+        }
+    }
+
+    // level <moves per session> <base time (minutes or minutes:seconds)> <increment (seconds)>
+    fn level(&mut self, arguments: &[&str]) {
+        let [moves_per_session, base_time, increment] = arguments else {
+            error!("Invalid level command! ({})", arguments.join(" "));
+            return;
+        };
+
+        let base_time = match base_time.split_once(':') {
+            Some((minutes, seconds)) => minutes
+                .parse::<u64>()
+                .ok()
+                .zip(seconds.parse::<u64>().ok())
+                .map(|(minutes, seconds)| minutes * 60 + seconds),
+            None => base_time.parse::<u64>().ok().map(|minutes| minutes * 60),
+        };
+
+        let increment = increment
+            .parse::<f32>()
+            .ok()
+            .and_then(|increment| Duration::try_from_secs_f32(increment).ok());
+
+        match (moves_per_session.parse::<u32>(), base_time, increment) {
+            (Ok(moves_per_session), Some(base_time), Some(increment)) => {
+                self.time_control = TimeControl {
+                    moves_per_session,
+                    base_time: Duration::from_secs(base_time),
+                    increment,
+                    move_time: None,
+                };
+            }
+            _ => error!("Invalid level command! ({})", arguments.join(" ")),
+        }
+    }
+
+    // Takes back plies by replaying the game without them
+    fn undo(&mut self, plies: usize) {
+        self.search_service.cancel();
+
+        let moves = &self.game.history[..self.game.history.len().saturating_sub(plies)];
+
+        match replay(&self.game.starting_fen, moves) {
+            Ok(game) => self.game = game,
+            Err(e) => error!("{}", e),
+        }
+    }
+
+    fn start_search_if_engine_to_move(&mut self) {
+        if self.force
+            || self.search_service.is_searching()
+            || self.game.result.is_over()
+            || self.game.side_to_move != self.engine_color
+        {
+            return;
+        }
+
+        let time_limit = match self.time_control.move_time {
+            Some(move_time) => move_time.saturating_sub(MOVE_OVERHEAD),
+            None => {
+                let time_control = &self.time_control;
+                let remaining_time = self.remaining_time.unwrap_or(time_control.base_time);
+
+                // Moves until the next time control (if there is one)
+                let moves_to_go = (time_control.moves_per_session > 0).then(|| {
+                    let moves_played = self.game.history.len() as u32 / 2;
+
+                    time_control.moves_per_session - moves_played % time_control.moves_per_session
+                });
+
+                time_for_move(remaining_time, time_control.increment, moves_to_go)
+            }
+        };
+
+        self.search_start = Instant::now();
+        self.search_service.start(
+            self.game.position(),
+            self.game.position_hashes.clone(),
+            self.evaluation.clone(),
+            SearchLimits {
+                max_depth: self.max_depth,
+                time_limit: Some(time_limit),
+            },
+        );
+    }
+
+    fn poll_search(&mut self) {
+        let Some(result) = self.search_service.poll() else {
+            return;
+        };
+
+        let Some(r#move) = result.best_move else {
+            return;
+        };
+
+        if self.post {
+            self.send_thinking(&result);
+        }
+
+        if let Err(e) = self.game.make_move(r#move) {
+            error!("{}", e);
+            return;
+        }
+
+        send(&format!("move {}", uci::format_move(r#move)));
+
+        self.send_result_if_over();
+    }
+
+    // <depth> <score> <time (centiseconds)> <nodes> <pv>
+    fn send_thinking(&self, result: &SearchResult) {
+        let best_move = result.best_move.map_or(String::new(), |r#move| {
+            san::format(&self.game.position(), r#move)
+        });
+
+        send(&format!(
+            "{} {} {} {} {}",
+            result.depth,
+            format_score(result.score),
+            self.search_start.elapsed().as_millis() / 10,
+            result.nodes,
+            best_move
+        ));
+    }
+
+    fn send_result_if_over(&mut self) {
+        let result = self.game.result;
+
+        if result.is_over() {
+            send(&format!("{} {{{}}}", pgn::result_marker(&result), result));
+        }
+    }
+}
+
+fn format_score(score: i32) -> i32 {
+    if score.abs() >= MATE_SCORE - MAX_PLY as i32 {
+        let moves = (MATE_SCORE - score.abs() + 1) / 2;
+
+        score.signum() * (CECP_MATE_SCORE + moves)
+    } else {
+        score
+    }
+}
+
+fn replay(starting_fen: &str, moves: &[Move]) -> Result<Game, Box<dyn Error>> {
+    let mut game = Game::from_fen(starting_fen)?;
+
+    for r#move in moves {
+        game.make_move(*r#move)?;
+    }
+
+    Ok(game)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xboard(commands: &[&str]) -> XBoard {
+        let mut xboard = XBoard::new();
+
+        for command in commands {
+            assert!(xboard.handle_command(command));
+        }
+
+        xboard
+    }
+
+    fn assert_time_control(
+        xboard: &XBoard,
+        moves_per_session: u32,
+        base_time: Duration,
+        increment: Duration,
+    ) {
+        let time_control = &xboard.time_control;
+
+        assert_eq!(time_control.moves_per_session, moves_per_session);
+        assert_eq!(time_control.base_time, base_time);
+        assert_eq!(time_control.increment, increment);
+    }
+
+    #[test]
+    fn level() {
+        let xboard = self::xboard(&["level 40 5 0"]);
+        assert_time_control(&xboard, 40, Duration::from_secs(300), Duration::ZERO);
+
+        let xboard = self::xboard(&["level 0 2:30 1.5"]);
+        assert_time_control(
+            &xboard,
+            0,
+            Duration::from_secs(150),
+            Duration::from_millis(1500),
+        );
+
+        // Invalid commands keep the previous time control
+        for level in [
+            "level 40 5",
+            "level 40 5 0 0",
+            "level x 5 0",
+            "level 40 five 0",
+            "level 40 5:xx 0",
+            "level 40 5 -1",
+            "level 40 5 inf",
+            "level 40 5 1e30",
+        ] {
+            let xboard = self::xboard(&["level 20 1 2", level]);
+            assert_time_control(&xboard, 20, Duration::from_secs(60), Duration::from_secs(2));
+        }
+    }
+
+    #[test]
+    fn st_sd_and_time() {
+        let xboard = self::xboard(&["st 10"]);
+        assert_eq!(xboard.time_control.move_time, Some(Duration::from_secs(10)));
+
+        for st in ["st", "st x", "st -1", "st inf", "st NaN", "st 1e30"] {
+            let xboard = self::xboard(&["st 2.5", st]);
+            assert_eq!(
+                xboard.time_control.move_time,
+                Some(Duration::from_millis(2500)),
+                "{st}"
+            );
+        }
+
+        assert_eq!(self::xboard(&["sd 5"]).max_depth, 5);
+        assert_eq!(self::xboard(&["sd 0"]).max_depth, 1);
+        assert_eq!(self::xboard(&["sd 1000"]).max_depth, MAX_PLY as u32);
+        assert_eq!(self::xboard(&["sd 5", "sd x"]).max_depth, 5);
+
+        // The remaining time is sent in centiseconds
+        let xboard = self::xboard(&["time 12345"]);
+        assert_eq!(xboard.remaining_time, Some(Duration::from_millis(123_450)));
+        assert_eq!(self::xboard(&["time 100", "time x"]).remaining_time, None);
+        assert!(self::xboard(&["time 18446744073709551615"])
+            .remaining_time
+            .is_some());
+    }
+
+    #[test]
+    fn user_moves() {
+        // Coordinate notation and SAN
+        let xboard = self::xboard(&[
+            "force",
+            "usermove e2e4",
+            "usermove e5",
+            "usermove g1f3",
+            "usermove Nc6",
+        ]);
+
+        assert_eq!(
+            xboard.game.to_fen(),
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3"
+        );
+
+        // Promotions in coordinate notation
+        let xboard = self::xboard(&[
+            "setboard 4k3/P7/8/8/8/8/8/4K3 w - - 0 1",
+            "force",
+            "usermove a7a8q",
+        ]);
+        assert_eq!(xboard.game.to_fen(), "Q3k3/8/8/8/8/8/8/4K3 b - - 0 1");
+    }
+
+    #[test]
+    fn undo_and_remove() {
+        let mut xboard =
+            self::xboard(&["force", "usermove e2e4", "usermove e7e5", "usermove g1f3"]);
+        assert_eq!(xboard.game.history.len(), 3);
+
+        xboard.handle_command("undo");
+        assert_eq!(xboard.game.history.len(), 2);
+        assert_eq!(xboard.game.side_to_move, PieceColor::White);
+
+        xboard.handle_command("remove");
+        assert_eq!(xboard.game.history.len(), 0);
+        assert_eq!(xboard.game.to_fen(), crate::fen::STARTING_FEN);
+
+        // Nothing to take back
+        xboard.handle_command("remove");
+        assert_eq!(xboard.game.history.len(), 0);
+    }
+
+    #[test]
+    fn scores() {
+        assert_eq!(format_score(35), 35);
+        assert_eq!(format_score(-120), -120);
+        assert_eq!(format_score(MATE_SCORE - 1), 100_001);
+        assert_eq!(format_score(MATE_SCORE - 3), 100_002);
+        assert_eq!(format_score(-(MATE_SCORE - 2)), -100_001);
+    }
+}
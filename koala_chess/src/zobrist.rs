@@ -0,0 +1,175 @@
+use crate::{
+    piece::{PieceColor, PieceKind},
+    rules::CastlingRights,
+};
+use std::sync::LazyLock;
+
+// Fixed seed, so that the keys (and thereby the hashes) are the same on every run
+const SEED: u64 = 0x4b6f_616c_6143_6873;
+
+pub static ZOBRIST_KEYS: LazyLock<ZobristKeys> = LazyLock::new(ZobristKeys::new);
+
+// Random keys which are combined via XOR to hash positions. Moves only have to toggle the keys of
+// the changed parts
+// Reference: https://www.chessprogramming.org/Zobrist_Hashing
+pub struct ZobristKeys {
+    // pieces[color][kind][board_y * 8 + board_x]
+    pieces: [[[u64; 64]; 6]; 2],
+    // White king side, white queen side, black king side, black queen side
+    castling_rights: [u64; 4],
+    en_passant_files: [u64; 8],
+    black_to_move: u64,
+}
+
+impl ZobristKeys {
+    fn new() -> ZobristKeys {
+        let mut state = SEED;
+        let mut next = || split_mix_64(&mut state);
+
+        let mut pieces = [[[0; 64]; 6]; 2];
+
+        for color_keys in pieces.iter_mut() {
+            for kind_keys in color_keys.iter_mut() {
+                for key in kind_keys.iter_mut() {
+                    *key = next();
+                }
+            }
+        }
+
+        ZobristKeys {
+            pieces,
+            castling_rights: [next(), next(), next(), next()],
+            en_passant_files: [
+                next(),
+                next(),
+                next(),
+                next(),
+                next(),
+                next(),
+                next(),
+                next(),
+            ],
+            black_to_move: next(),
+        }
+    }
+
+    pub fn piece(&self, color: PieceColor, kind: PieceKind, board_x: u8, board_y: u8) -> u64 {
+        self.pieces[color.index()][kind.index()][board_y as usize * 8 + board_x as usize]
+    }
+
+    pub fn castling_rights(&self, castling_rights: &CastlingRights) -> u64 {
+        [
+            castling_rights.white_king_side,
+            castling_rights.white_queen_side,
+            castling_rights.black_king_side,
+            castling_rights.black_queen_side,
+        ]
+        .iter()
+        .zip(self.castling_rights)
+        .filter(|(right, _)| **right)
+        .fold(0, |hash, (_, key)| hash ^ key)
+    }
+
+    pub fn en_passant_file(&self, board_x: u8) -> u64 {
+        self.en_passant_files[board_x as usize]
+    }
+
+    pub fn side_to_move(&self, side_to_move: PieceColor) -> u64 {
+        match side_to_move {
+            PieceColor::White => 0,
+            PieceColor::Black => self.black_to_move,
+        }
+    }
+}
+
+// Reference: https://prng.di.unimi.it/splitmix64.c
+fn split_mix_64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{fen, rules::Position, uci};
+
+    fn position(fen: &str) -> Position {
+        let fen = fen::parse(fen).unwrap();
+
+        Position::new(&fen.pieces, fen.side_to_move, fen.en_passant_target)
+    }
+
+    // The hash of a position which is set up from scratch (see Position::compute_hash)
+    fn computed_hash(position: &Position) -> u64 {
+        self::position(&fen::format(position, 0, 1)).hash()
+    }
+
+    #[test]
+    fn incremental_hash_equals_computed_hash() {
+        let mut position = position("r3k2r/1P6/8/8/3p4/8/4P3/R3K2R w KQkq - 0 1");
+        let mut hashes = vec![position.hash()];
+        let mut moves = Vec::new();
+
+        // Double pawn push, en passant, castling on both sides, promotion with capture of a rook
+        // (which removes a castling right) and a king move (which removes the others)
+        for text in [
+            "e2e4", "d4e3", "e1c1", "e8g8", "b7a8q", "e3e2", "a8f8", "g8f8",
+        ] {
+            let r#move = uci::parse_move(&position, text).unwrap();
+            let undo = position.make_move(r#move);
+
+            assert_eq!(position.hash(), computed_hash(&position), "{text}");
+
+            hashes.push(position.hash());
+            moves.push((r#move, undo));
+        }
+
+        // Unmaking the moves restores the hashes
+        while let Some((r#move, undo)) = moves.pop() {
+            hashes.pop();
+            position.unmake_move(r#move, undo);
+
+            assert_eq!(Some(&position.hash()), hashes.last());
+        }
+    }
+
+    #[test]
+    fn hash_depends_on_the_whole_position() {
+        let hash = |fen| position(fen).hash();
+        let start = hash("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        // Side to move, castling rights and en passant square
+        assert_ne!(
+            start,
+            hash("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1")
+        );
+        assert_ne!(
+            start,
+            hash("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Kkq - 0 1")
+        );
+        assert_ne!(
+            hash("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 1"),
+            hash("4k3/8/8/3Pp3/8/8/8/4K3 w - - 0 1")
+        );
+
+        // The same position reached by different move orders
+        let mut position = position("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let mut transposed = position.clone();
+
+        for (position, moves) in [
+            (&mut position, ["g1f3", "g8f6", "b1c3"]),
+            (&mut transposed, ["b1c3", "g8f6", "g1f3"]),
+        ] {
+            for text in moves {
+                let r#move = uci::parse_move(position, text).unwrap();
+                position.make_move(r#move);
+            }
+        }
+
+        assert_eq!(position.hash(), transposed.hash());
+    }
+}
@@ -7,6 +7,8 @@ in vec2 texture_coordinate;
 uniform sampler2D uniform_texture;
 uniform float piece_x;
 uniform float piece_y;
+// 0.0 = invisible, 1.0 = opaque (e.g. to fade out captured pieces)
+uniform float alpha;
 
 out vec4 fragment_color;
 
@@ -21,4 +23,5 @@ void main()
     float corrected_piece_y = piece_y + 1.0;
 
     fragment_color = texture(uniform_texture, vec2((texture_coordinate.x + piece_x) * scale, (texture_coordinate.y + corrected_piece_y) * scale));
+    fragment_color.a *= alpha;
 }
\ No newline at end of file
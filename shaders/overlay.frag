@@ -0,0 +1,36 @@
+#version 300 es
+precision mediump float;
+
+in vec2 texture_coordinate;
+
+uniform vec4 color;
+// 0.0 = square, 1.0 = dot, 2.0 = ring, 3.0 = glow, 4.0 = frame
+uniform float shape;
+
+out vec4 fragment_color;
+
+void main()
+{
+    float distance_to_center = distance(texture_coordinate, vec2(0.5, 0.5));
+    float alpha = color.a;
+
+    if (shape == 1.0) {
+        if (distance_to_center > 0.15) {
+            discard;
+        }
+    } else if (shape == 2.0) {
+        if (distance_to_center < 0.42 || distance_to_center > 0.5) {
+            discard;
+        }
+    } else if (shape == 3.0) {
+        alpha *= 1.0 - smoothstep(0.0, 0.5, distance_to_center);
+    } else if (shape == 4.0) {
+        vec2 distance_to_edge = min(texture_coordinate, 1.0 - texture_coordinate);
+
+        if (min(distance_to_edge.x, distance_to_edge.y) > 0.06) {
+            discard;
+        }
+    }
+
+    fragment_color = vec4(color.rgb, alpha);
+}
\ No newline at end of file
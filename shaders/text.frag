@@ -0,0 +1,25 @@
+#version 300 es
+precision mediump float;
+
+in vec2 texture_coordinate;
+
+uniform sampler2D uniform_texture;
+// Cell of the glyph in the font atlas (row from the top)
+uniform float glyph_x;
+uniform float glyph_y;
+uniform vec4 color;
+
+out vec4 fragment_color;
+
+const float columns = 16.0;
+const float rows = 8.0;
+
+void main()
+{
+    // The bitmap rows are stored from the bottom to the top
+    float s = (glyph_x + texture_coordinate.x) / columns;
+    float t = 1.0 - (glyph_y + 1.0 - texture_coordinate.y) / rows;
+
+    // The glyphs are white, so only their coverage (alpha) is used
+    fragment_color = vec4(color.rgb, color.a * texture(uniform_texture, vec2(s, t)).a);
+}
\ No newline at end of file